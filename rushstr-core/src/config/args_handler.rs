@@ -1,4 +1,4 @@
-use crate::{ConfigOptions, configure_zsh_profile, delete_db, print_settings};
+use crate::{ConfigOptions, UserConfig, configure_zsh_profile, delete_db, print_settings, reset_learning};
 
 pub struct ArgsHandler {
     options: ConfigOptions,
@@ -12,11 +12,17 @@ impl ArgsHandler {
     /// returns true if we should exit the execution
     pub fn execute(&self) -> anyhow::Result<bool> {
         if self.options.reset_settings {
-            delete_db()?;
+            delete_db(self.options.data_dir.as_deref())?;
+            return Ok(true);
+        }
+        if self.options.reset_learning {
+            reset_learning(self.options.data_dir.as_deref())?;
             return Ok(true);
         }
         if self.options.show_settings {
-            print_settings()?;
+            let config_file = UserConfig::file_path(&self.options)?;
+            let config = UserConfig::from_options(&self.options)?;
+            print_settings(self.options.data_dir.as_deref(), &config_file, &config)?;
             return Ok(true);
         }
         if self.options.zsh_shell_conf {
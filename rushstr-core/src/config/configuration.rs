@@ -1,11 +1,15 @@
+use std::path::PathBuf;
+
 use clap::Parser;
 
+use crate::{CaseMode, MIN_UI_HEIGHT, RankingType, SearchScope, SearchType};
+
 /// Represents the different types of files used internally by rushstr.
 pub enum RushstrFiles {
     /// The filename for the rushstr command usage database.
     DbName,
-    /// The filename for the rushstr settings/configuration file.
-    Settings,
+    /// The filename for the rushstr configuration file.
+    Config,
 }
 
 impl RushstrFiles {
@@ -13,7 +17,7 @@ impl RushstrFiles {
     pub fn val(self) -> &'static str {
         match self {
             RushstrFiles::DbName => "rushstr.db",
-            RushstrFiles::Settings => "settings.hex",
+            RushstrFiles::Config => "config.toml",
         }
     }
 
@@ -27,7 +31,7 @@ impl RushstrFiles {
     }
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, Default)]
 #[command(
     name = "rushstr",
     version = env!("CARGO_PKG_VERSION"),
@@ -38,6 +42,11 @@ pub struct ConfigOptions {
     #[arg(long)]
     pub reset_settings: bool,
 
+    /// Forget the commands learned for the queries typed, keeping the rest
+    /// of the usage history
+    #[arg(long)]
+    pub reset_learning: bool,
+
     /// Show current configuration
     #[arg(long)]
     pub show_settings: bool,
@@ -45,4 +54,43 @@ pub struct ConfigOptions {
     /// update the zsh config for better integration with rushstr
     #[arg(long)]
     pub zsh_shell_conf: bool,
+
+    /// Directory holding the usage database (overrides $RUSHSTR_HOME and
+    /// $XDG_DATA_HOME)
+    #[arg(long, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Configuration file to use instead of config.toml in the config
+    /// directory
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Search type to start with, e.g. monkey_typing, exact, regex, typo or
+    /// glob
+    #[arg(long)]
+    pub search_type: Option<SearchType>,
+
+    /// Start with only favorites shown
+    #[arg(long)]
+    pub favorites: bool,
+
+    /// Case sensitivity policy
+    #[arg(long, value_enum)]
+    pub case: Option<CaseMode>,
+
+    /// Text searched, the flattened command or its raw lines
+    #[arg(long, value_enum)]
+    pub scope: Option<SearchScope>,
+
+    /// Order of the results
+    #[arg(long, value_enum)]
+    pub ranking: Option<RankingType>,
+
+    /// Maximum number of results shown
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_results: Option<usize>,
+
+    /// Number of terminal rows used by the UI (full screen by default)
+    #[arg(long, value_name = "ROWS", value_parser = clap::value_parser!(u16).range(MIN_UI_HEIGHT as i64..))]
+    pub height: Option<u16>,
 }
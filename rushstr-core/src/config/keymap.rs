@@ -0,0 +1,538 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// Something the search UI does when a key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Clears the marks, or quits without output when nothing is marked.
+    Cancel,
+    /// Outputs the selected command, or the marked ones joined with `&&`.
+    Accept,
+    /// Outputs the selected command, or the marked ones joined with
+    /// newlines.
+    AcceptLines,
+    /// Copies the selected or marked commands to the clipboard and quits.
+    Copy,
+    /// Marks the selected or marked commands as favorites, or unmarks them.
+    ToggleFavorite,
+    /// Shows only favorites, or everything.
+    FavoritesOnly,
+    /// Switches to the next search type.
+    CycleMode,
+    /// Switches to the next ordering of the results.
+    CycleRanking,
+    /// Switches to the next case mode.
+    CycleCase,
+    /// Searches the flattened command or its raw lines.
+    ToggleScope,
+    /// Lists the recent searches.
+    RecentSearches,
+    /// Moves the selection up, or recalls an older search.
+    Up,
+    /// Moves the selection down.
+    Down,
+    /// Recalls a newer search, or moves the selection down.
+    RecallNewer,
+    PageUp,
+    PageDown,
+    HalfPageUp,
+    HalfPageDown,
+    /// Selects the first command.
+    First,
+    /// Selects the last command.
+    Last,
+    /// Marks the selected command, or unmarks it.
+    Mark,
+    /// Tags the selected or marked commands.
+    Tag,
+    /// Deletes the selected or marked commands.
+    Delete,
+    TogglePreview,
+    ToggleDebug,
+}
+
+impl Action {
+    /// Every action, in the order their bindings are resolved.
+    pub const ALL: [Action; 25] = [
+        Action::Cancel,
+        Action::Accept,
+        Action::AcceptLines,
+        Action::Copy,
+        Action::ToggleFavorite,
+        Action::FavoritesOnly,
+        Action::CycleMode,
+        Action::CycleRanking,
+        Action::CycleCase,
+        Action::ToggleScope,
+        Action::RecentSearches,
+        Action::Up,
+        Action::Down,
+        Action::RecallNewer,
+        Action::PageUp,
+        Action::PageDown,
+        Action::HalfPageUp,
+        Action::HalfPageDown,
+        Action::First,
+        Action::Last,
+        Action::Mark,
+        Action::Tag,
+        Action::Delete,
+        Action::TogglePreview,
+        Action::ToggleDebug,
+    ];
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Action::Cancel => "cancel",
+            Action::Accept => "accept",
+            Action::AcceptLines => "accept_lines",
+            Action::Copy => "copy",
+            Action::ToggleFavorite => "toggle_favorite",
+            Action::FavoritesOnly => "favorites_only",
+            Action::CycleMode => "cycle_mode",
+            Action::CycleRanking => "cycle_ranking",
+            Action::CycleCase => "cycle_case",
+            Action::ToggleScope => "toggle_scope",
+            Action::RecentSearches => "recent_searches",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::RecallNewer => "recall_newer",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::HalfPageUp => "half_page_up",
+            Action::HalfPageDown => "half_page_down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Mark => "mark",
+            Action::Tag => "tag",
+            Action::Delete => "delete",
+            Action::TogglePreview => "toggle_preview",
+            Action::ToggleDebug => "toggle_debug",
+        }
+    }
+
+    /// Returns the keys bound to the action when the configuration does not
+    /// bind it.
+    pub fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Cancel => &["esc"],
+            Action::Accept => &["enter"],
+            Action::AcceptLines => &["alt-enter"],
+            Action::Copy => &["ctrl-c"],
+            Action::ToggleFavorite => &["ctrl-x"],
+            Action::FavoritesOnly => &["ctrl-f"],
+            Action::CycleMode => &["ctrl-t"],
+            Action::CycleRanking => &["ctrl-o"],
+            Action::CycleCase => &["ctrl-s"],
+            Action::ToggleScope => &["ctrl-l"],
+            Action::RecentSearches => &["ctrl-r"],
+            Action::Up => &["up", "ctrl-p"],
+            Action::Down => &["down"],
+            Action::RecallNewer => &["ctrl-n"],
+            Action::PageUp => &["pageup"],
+            Action::PageDown => &["pagedown"],
+            Action::HalfPageUp => &["ctrl-u"],
+            Action::HalfPageDown => &["ctrl-d"],
+            Action::First => &["home"],
+            Action::Last => &["end"],
+            Action::Mark => &["tab"],
+            Action::Tag => &["ctrl-g"],
+            Action::Delete => &["shift-delete"],
+            Action::TogglePreview => &["f2"],
+            Action::ToggleDebug => &["f12"],
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+/// Names of the keys other than characters.
+const NAMED_KEYS: [&str; 16] = [
+    "enter",
+    "esc",
+    "tab",
+    "backspace",
+    "delete",
+    "insert",
+    "home",
+    "end",
+    "pageup",
+    "pagedown",
+    "up",
+    "down",
+    "left",
+    "right",
+    "space",
+    "backtab",
+];
+
+/// Returns the canonical name of a key: `ctrl-`, `alt-` and `shift-`, in
+/// this order, before the name of the key or the character it types.
+///
+/// Front ends name the keys they receive with it, so they can be looked up
+/// in a `Keymap`.
+pub fn key_name(ctrl: bool, alt: bool, shift: bool, key: &str) -> String {
+    let mut name = String::new();
+    for (held, modifier) in [(ctrl, "ctrl-"), (alt, "alt-"), (shift, "shift-")] {
+        if held {
+            name.push_str(modifier);
+        }
+    }
+    name.push_str(key);
+    name
+}
+
+/// Parses a key as written in the configuration, such as `ctrl-t`,
+/// `Alt+Enter` or `F2`, into its canonical name.
+///
+/// Modifiers are `ctrl`, `alt` and `shift`, joined with `-` or `+`. Keys
+/// are a character or one of `enter`, `esc`, `tab`, `backspace`, `delete`,
+/// `insert`, `home`, `end`, `pageup`, `pagedown`, `up`, `down`, `left`,
+/// `right`, `space` and `f1` to `f12`. A shifted letter is its uppercase
+/// character: `shift-t` is `T` and `ctrl-shift-t` is `ctrl-T`, while
+/// `ctrl-T` is `ctrl-t`.
+pub fn parse_key(text: &str) -> Result<String, String> {
+    let (mut ctrl, mut alt, mut shift) = (false, false, false);
+    let mut rest = text;
+    loop {
+        let lower = rest.to_lowercase();
+        let modifier = ["ctrl", "control", "alt", "meta", "shift"]
+            .into_iter()
+            .find(|modifier| {
+                lower.len() > modifier.len() + 1
+                    && lower.starts_with(modifier)
+                    && lower[modifier.len()..].starts_with(['-', '+'])
+            });
+        let Some(modifier) = modifier else {
+            break;
+        };
+        match modifier {
+            "ctrl" | "control" => ctrl = true,
+            "alt" | "meta" => alt = true,
+            _ => shift = true,
+        }
+        rest = &rest[modifier.len() + 1..];
+    }
+
+    let mut chars = rest.chars();
+    let key = match (chars.next(), chars.next()) {
+        (None, _) => return Err(format!("`{text}` names no key")),
+        (Some(c), None) if shift && c.is_alphabetic() => {
+            shift = false;
+            c.to_uppercase().to_string()
+        },
+        (Some(_), None) if shift => return Err(format!("`{text}`: write the shifted character instead")),
+        (Some(' '), None) => "space".to_string(),
+        // terminals send Ctrl+T as a lowercase `t`
+        (Some(c), None) if ctrl => c.to_lowercase().to_string(),
+        (Some(c), None) => c.to_string(),
+        _ => {
+            let lower = rest.to_lowercase();
+            let alias = match lower.as_str() {
+                "return" => "enter",
+                "escape" => "esc",
+                "del" => "delete",
+                "ins" => "insert",
+                "pgup" => "pageup",
+                "pgdn" | "pgdown" => "pagedown",
+                other => other,
+            };
+            let function = alias
+                .strip_prefix('f')
+                .and_then(|number| number.parse::<u8>().ok())
+                .is_some_and(|number| (1..=12).contains(&number));
+            if !function && !NAMED_KEYS.contains(&alias) {
+                return Err(format!("unknown key `{rest}`"));
+            }
+            alias.to_string()
+        },
+    };
+    // shift-tab arrives as a key of its own
+    if key == "backtab" {
+        return Ok(key_name(ctrl, alt, true, "tab"));
+    }
+    Ok(key_name(ctrl, alt, shift, &key))
+}
+
+/// The keys of the search box and of quick select, which are not actions,
+/// with what they do. Binding one of them to an action is reported as a
+/// conflict.
+const RESERVED_KEYS: [(&str, &str); 18] = [
+    ("left", "moving the cursor left"),
+    ("right", "moving the cursor right"),
+    ("ctrl-left", "moving the cursor a word left"),
+    ("ctrl-right", "moving the cursor a word right"),
+    ("alt-b", "moving the cursor a word left"),
+    ("alt-f", "moving the cursor a word right"),
+    ("ctrl-a", "moving the cursor to the start"),
+    ("ctrl-e", "moving the cursor to the end"),
+    ("backspace", "deleting the character before the cursor"),
+    ("delete", "deleting the character under the cursor"),
+    ("ctrl-backspace", "killing the word before the cursor"),
+    ("alt-backspace", "killing the word before the cursor"),
+    ("ctrl-w", "killing the word before the cursor"),
+    ("alt-d", "killing the word after the cursor"),
+    ("alt-u", "killing up to the start"),
+    ("ctrl-k", "killing up to the end"),
+    ("ctrl-y", "yanking the last killed text"),
+    ("alt-y", "yanking an older killed text"),
+];
+
+/// Returns what the key with the canonical name `key` does when no action
+/// is bound to it: editing the search box, typing a character or selecting
+/// one of the first ten commands.
+fn reserved_use(key: &str) -> Option<&'static str> {
+    if let Some((_, what)) = RESERVED_KEYS.iter().find(|(name, _)| *name == key) {
+        return Some(what);
+    }
+    if key == "space" || key.chars().count() == 1 {
+        return Some("typing it in the search box");
+    }
+    let mut digit = key.strip_prefix("alt-")?.chars();
+    match (digit.next(), digit.next()) {
+        (Some(c), None) if c.is_ascii_digit() => Some("selecting a command by its number"),
+        _ => None,
+    }
+}
+
+/// What a key is used for besides the action it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyUse {
+    /// Another action the key is bound to.
+    Action(Action),
+    /// What the key does in the search box, or in quick select, when no
+    /// action is bound to it.
+    Reserved(&'static str),
+}
+
+/// A key bound to several actions, or bound to an action while it also
+/// edits the search box.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyConflict {
+    /// The canonical name of the key.
+    pub key: String,
+    /// The action the key runs.
+    pub kept: Action,
+    /// What the key no longer does.
+    pub dropped: KeyUse,
+}
+
+impl Display for KeyConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.dropped {
+            KeyUse::Action(dropped) => {
+                write!(
+                    f,
+                    "`{}` is bound to `{}` and `{dropped}`, it runs `{}`",
+                    self.key, self.kept, self.kept
+                )
+            },
+            KeyUse::Reserved(what) => write!(f, "`{}` is bound to `{}` instead of {what}", self.key, self.kept),
+        }
+    }
+}
+
+/// The actions run by the keys of the search UI.
+///
+/// The list of recent searches and the tag prompt follow it too: `up`,
+/// `down`, `accept` and `cancel` move, confirm and close them. Keys that type
+/// text, the `y` confirming a deletion and `alt-0`…`alt-9` are fixed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<String, Action>,
+    conflicts: Vec<KeyConflict>,
+}
+
+impl Keymap {
+    /// Builds the keymap from the `[keys]` table: an action listed there is
+    /// bound to the given keys only, the others keep their default keys.
+    ///
+    /// A key bound to several actions runs the one the configuration binds
+    /// it to, or the first one in `Action::ALL`, and is reported in
+    /// `conflicts`. So is a key of the search box bound to an action, which
+    /// runs the action. Keys that do not parse are skipped, `UserConfig::parse`
+    /// reports them.
+    pub fn new(keys: &BTreeMap<Action, Vec<String>>) -> Keymap {
+        let mut keymap = Keymap::default();
+        let mut configured = HashMap::new();
+        for action in Action::ALL {
+            let names: Vec<String> = match keys.get(&action) {
+                Some(names) => names.iter().filter_map(|name| parse_key(name).ok()).collect(),
+                None => {
+                    action
+                        .default_keys()
+                        .iter()
+                        .filter_map(|name| parse_key(name).ok())
+                        .collect()
+                },
+            };
+            for name in names {
+                let is_configured = keys.contains_key(&action);
+                match keymap.bindings.get(&name).copied() {
+                    None => {
+                        keymap.bindings.insert(name.clone(), action);
+                        configured.insert(name, is_configured);
+                    },
+                    Some(bound) if bound == action => {},
+                    Some(bound) if is_configured && !configured[&name] => {
+                        keymap.conflicts.push(KeyConflict {
+                            key: name.clone(),
+                            kept: action,
+                            dropped: KeyUse::Action(bound),
+                        });
+                        keymap.bindings.insert(name.clone(), action);
+                        configured.insert(name, true);
+                    },
+                    Some(bound) => {
+                        keymap.conflicts.push(KeyConflict {
+                            key: name,
+                            kept: bound,
+                            dropped: KeyUse::Action(action),
+                        })
+                    },
+                }
+            }
+        }
+        let mut reserved: Vec<KeyConflict> = keymap
+            .bindings
+            .iter()
+            .filter_map(|(key, action)| {
+                reserved_use(key).map(|what| {
+                    KeyConflict {
+                        key: key.clone(),
+                        kept: *action,
+                        dropped: KeyUse::Reserved(what),
+                    }
+                })
+            })
+            .collect();
+        reserved.sort_by(|a, b| a.key.cmp(&b.key));
+        keymap.conflicts.extend(reserved);
+        keymap
+    }
+
+    /// Returns the action run by the key with the canonical name `key`.
+    pub fn action(&self, key: &str) -> Option<Action> {
+        self.bindings.get(key).copied()
+    }
+
+    /// Returns the keys running `action`, sorted.
+    pub fn keys(&self, action: Action) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| key.as_str())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Returns the keys bound to several actions, or to an action and the
+    /// search box.
+    pub fn conflicts(&self) -> &[KeyConflict] {
+        &self.conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("ctrl-t").unwrap(), "ctrl-t");
+        assert_eq!(parse_key("Alt+Enter").unwrap(), "alt-enter");
+        assert_eq!(parse_key("control-alt-x").unwrap(), "ctrl-alt-x");
+        assert_eq!(parse_key("alt-ctrl-x").unwrap(), "ctrl-alt-x");
+        assert_eq!(parse_key("shift-t").unwrap(), "T");
+        assert_eq!(parse_key("Ctrl+T").unwrap(), "ctrl-t");
+        assert_eq!(parse_key("ctrl-shift-t").unwrap(), "ctrl-T");
+        assert_eq!(parse_key("alt-T").unwrap(), "alt-T");
+        assert_eq!(parse_key("shift-tab").unwrap(), "shift-tab");
+        assert_eq!(parse_key("BackTab").unwrap(), "shift-tab");
+        assert_eq!(parse_key("shift-Delete").unwrap(), "shift-delete");
+        assert_eq!(parse_key("PgDn").unwrap(), "pagedown");
+        assert_eq!(parse_key("F12").unwrap(), "f12");
+        assert_eq!(parse_key("ctrl--").unwrap(), "ctrl--");
+        assert_eq!(parse_key(" ").unwrap(), "space");
+        assert!(parse_key("ctrl-").is_err());
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("hyper-x").is_err());
+        assert!(parse_key("shift-1").is_err());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let keymap = Keymap::new(&BTreeMap::new());
+        assert!(keymap.conflicts().is_empty());
+        for action in Action::ALL {
+            assert!(!keymap.keys(action).is_empty(), "{action} has no default key");
+        }
+        assert_eq!(keymap.action("ctrl-t"), Some(Action::CycleMode));
+        assert_eq!(keymap.keys(Action::Up), ["ctrl-p", "up"]);
+    }
+
+    #[test]
+    fn test_overrides_and_conflicts() {
+        let keys = BTreeMap::from([
+            (Action::CycleMode, vec!["alt-m".to_string()]),
+            (Action::Cancel, vec!["esc".to_string(), "ctrl-c".to_string()]),
+            (Action::Copy, vec!["ctrl-c".to_string()]),
+            (Action::Tag, vec!["ctrl-x".to_string()]),
+        ]);
+        let keymap = Keymap::new(&keys);
+        assert_eq!(keymap.action("ctrl-t"), None);
+        assert_eq!(keymap.action("alt-m"), Some(Action::CycleMode));
+        // two configured actions: the first one wins
+        assert_eq!(keymap.action("ctrl-c"), Some(Action::Cancel));
+        // a configured action wins over a default one
+        assert_eq!(keymap.action("ctrl-x"), Some(Action::Tag));
+        assert_eq!(
+            keymap.conflicts(),
+            [
+                KeyConflict {
+                    key: "ctrl-c".to_string(),
+                    kept: Action::Cancel,
+                    dropped: KeyUse::Action(Action::Copy),
+                },
+                KeyConflict {
+                    key: "ctrl-x".to_string(),
+                    kept: Action::Tag,
+                    dropped: KeyUse::Action(Action::ToggleFavorite),
+                },
+            ]
+        );
+        assert_eq!(
+            keymap.conflicts()[0].to_string(),
+            "`ctrl-c` is bound to `cancel` and `copy`, it runs `cancel`"
+        );
+    }
+
+    #[test]
+    fn test_reserved_keys() {
+        let keys = BTreeMap::from([
+            (Action::Copy, vec!["ctrl-y".to_string()]),
+            (Action::First, vec!["ctrl-a".to_string(), "home".to_string()]),
+            (Action::Mark, vec!["x".to_string(), "alt-1".to_string()]),
+        ]);
+        let keymap = Keymap::new(&keys);
+        // the action still runs, the lost search box key is reported
+        assert_eq!(keymap.action("ctrl-y"), Some(Action::Copy));
+        let reported: Vec<&str> = keymap.conflicts().iter().map(|c| c.key.as_str()).collect();
+        assert_eq!(reported, ["alt-1", "ctrl-a", "ctrl-y", "x"]);
+        let conflict = keymap.conflicts().iter().find(|c| c.key == "ctrl-y").unwrap();
+        assert_eq!(conflict.dropped, KeyUse::Reserved("yanking the last killed text"));
+        assert_eq!(
+            conflict.to_string(),
+            "`ctrl-y` is bound to `copy` instead of yanking the last killed text"
+        );
+    }
+}
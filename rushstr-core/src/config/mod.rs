@@ -1,3 +1,6 @@
 pub mod args_handler;
 pub mod configuration;
+pub mod keymap;
 pub mod search_options;
+pub mod theme_config;
+pub mod user_config;
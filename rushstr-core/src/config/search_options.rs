@@ -1,24 +1,157 @@
-#[derive(Debug, Clone)]
-pub enum SearchType {
-    MonkeyTyping,
-    Exact,
-    Regex,
-}
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use bincode::de::Decoder;
+use bincode::error::DecodeError;
+use bincode::{Decode, Encode, impl_borrow_decode};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{Filter, HItem, parse_filters};
+
+/// Name of the `Matcher` that runs the plain terms of a query.
+///
+/// The built-in search types are available as constants, library users can
+/// add their own through a `MatcherRegistry`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Encode)]
+#[serde(transparent)]
+pub struct SearchType(Cow<'static, str>);
 
 impl SearchType {
+    pub const EXACT: SearchType = SearchType(Cow::Borrowed("exact"));
+    pub const GLOB: SearchType = SearchType(Cow::Borrowed("glob"));
+    pub const MONKEY_TYPING: SearchType = SearchType(Cow::Borrowed("monkey_typing"));
+    pub const REGEX: SearchType = SearchType(Cow::Borrowed("regex"));
+    pub const TYPO: SearchType = SearchType(Cow::Borrowed("typo"));
+
+    pub fn new(name: impl Into<String>) -> Self {
+        SearchType(Cow::Owned(name.into()))
+    }
+
+    pub fn to_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for SearchType {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(SearchType::new(name))
+    }
+}
+
+impl<Context> Decode<Context> for SearchType {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(SearchType::new(String::decode(decoder)?))
+    }
+}
+
+impl_borrow_decode!(SearchType);
+
+impl Display for SearchType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Selects the built-in ranker that orders the results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingType {
+    /// Items selected often and recently first.
+    Frecency,
+    /// Most recent history entries first.
+    Recency,
+    /// Most selected items first.
+    Hits,
+    /// Best match first.
+    Score,
+    /// Commands in alphabetical order.
+    Alphabetical,
+    /// Shortest commands first.
+    Length,
+}
+
+impl RankingType {
     pub fn to_str(&self) -> &'static str {
         match self {
-            SearchType::MonkeyTyping => "monkey_typing",
-            SearchType::Exact => "exact",
-            SearchType::Regex => "regex",
+            RankingType::Frecency => "frecency",
+            RankingType::Recency => "recency",
+            RankingType::Hits => "hits",
+            RankingType::Score => "score",
+            RankingType::Alphabetical => "alphabetical",
+            RankingType::Length => "length",
         }
     }
 
     pub fn next(&self) -> Self {
         match self {
-            SearchType::MonkeyTyping => SearchType::Exact,
-            SearchType::Exact => SearchType::Regex,
-            SearchType::Regex => SearchType::MonkeyTyping,
+            RankingType::Frecency => RankingType::Recency,
+            RankingType::Recency => RankingType::Hits,
+            RankingType::Hits => RankingType::Score,
+            RankingType::Score => RankingType::Alphabetical,
+            RankingType::Alphabetical => RankingType::Length,
+            RankingType::Length => RankingType::Frecency,
+        }
+    }
+}
+
+/// Controls how letter case is compared while matching.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseMode {
+    /// Case always matters.
+    Sensitive,
+    /// Case never matters.
+    Insensitive,
+    /// Case matters only when the input contains an uppercase letter.
+    Smart,
+}
+
+impl CaseMode {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            CaseMode::Sensitive => "sensitive",
+            CaseMode::Insensitive => "insensitive",
+            CaseMode::Smart => "smart",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            CaseMode::Smart => CaseMode::Insensitive,
+            CaseMode::Insensitive => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Smart,
+        }
+    }
+}
+
+/// Selects the text of an item the query runs on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchScope {
+    /// The command on a single line, whitespace collapsed.
+    Command,
+    /// The lines of the command as typed, `^` and `$` of regular
+    /// expressions match at every line.
+    Raw,
+}
+
+impl SearchScope {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            SearchScope::Command => "command",
+            SearchScope::Raw => "raw",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SearchScope::Command => SearchScope::Raw,
+            SearchScope::Raw => SearchScope::Command,
         }
     }
 }
@@ -29,25 +162,83 @@ pub struct SearchOptions {
     pub input: String,
     /// type of pattern matching
     pub search_type: SearchType,
-    pub favorites: bool,
+    /// conditions on the item metadata, on top of the ones typed in `input`
+    pub filters: Vec<Filter>,
+    /// case policy used by every search type
+    pub case_mode: CaseMode,
+    /// text of the items searched
+    pub scope: SearchScope,
+    /// maximum number of items returned, `None` for no limit
+    pub max_results: Option<usize>,
+    /// order of the results
+    pub ranking: RankingType,
+    /// seconds after which the weight of a hit is halved when ranking
+    pub frecency_half_life: u64,
 }
 
+/// Default frecency half-life, two weeks.
+pub const DEFAULT_HALF_LIFE_DAYS: u64 = 14;
+/// Seconds in a day.
+pub(crate) const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
 impl Default for SearchOptions {
     fn default() -> Self {
         SearchOptions {
             input: "".to_string(),
-            search_type: SearchType::MonkeyTyping,
-            favorites: false,
+            search_type: SearchType::MONKEY_TYPING,
+            filters: Vec::new(),
+            case_mode: CaseMode::Smart,
+            scope: SearchScope::Command,
+            max_results: None,
+            ranking: RankingType::Frecency,
+            frecency_half_life: DEFAULT_HALF_LIFE_DAYS * SECS_PER_DAY,
         }
     }
 }
 
 impl SearchOptions {
+    /// Returns true if only favorites are shown.
+    pub fn favorites_only(&self) -> bool {
+        self.filters.contains(&Filter::Favorite)
+    }
+
+    /// Shows only favorites, or every item.
+    pub fn set_favorites_only(&mut self, favorites: bool) {
+        self.filters.retain(|filter| *filter != Filter::Favorite);
+        if favorites {
+            self.filters.push(Filter::Favorite);
+        }
+    }
+
+    /// Returns the text of `item` searched with these options.
+    pub fn searched_text(&self, item: &HItem) -> String {
+        match self.scope {
+            SearchScope::Command => item.command(),
+            SearchScope::Raw => item.raw_text(),
+        }
+    }
+
+    /// Returns true if the search ignores case.
+    ///
+    /// In smart mode case is ignored until a term has an uppercase letter:
+    /// like ripgrep, filters such as `tag:Build` and the escape sequences of
+    /// a regular expression such as `\S` do not count.
     pub fn is_case_insensitive(&self) -> bool {
-        match self.search_type {
-            SearchType::MonkeyTyping => true,
-            SearchType::Exact => false,
-            SearchType::Regex => true,
+        match self.case_mode {
+            CaseMode::Sensitive => false,
+            CaseMode::Insensitive => true,
+            CaseMode::Smart => {
+                let terms = parse_filters(&self.input).map_or_else(|_| self.input.clone(), |input| input.text);
+                let mut chars = terms.chars();
+                while let Some(c) = chars.next() {
+                    if c == '\\' && self.search_type == SearchType::REGEX {
+                        chars.next();
+                    } else if c.is_uppercase() {
+                        return false;
+                    }
+                }
+                true
+            },
         }
     }
 }
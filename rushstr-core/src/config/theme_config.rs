@@ -0,0 +1,272 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// A built-in colour theme of the search UI.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeName {
+    /// Made for dark terminals.
+    #[default]
+    Default,
+    /// Made for light terminals.
+    Light,
+    /// The Solarized palette, readable on its dark and light backgrounds.
+    Solarized,
+    /// No colours, only bold, underlined and reversed text.
+    Mono,
+}
+
+impl ThemeName {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ThemeName::Default => "default",
+            ThemeName::Light => "light",
+            ThemeName::Solarized => "solarized",
+            ThemeName::Mono => "mono",
+        }
+    }
+}
+
+impl Display for ThemeName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+/// Something of the search UI whose style a theme sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeElement {
+    /// The selected command, and the selected recent search.
+    Selection,
+    /// The characters matched by the search.
+    Match,
+    /// The info bar.
+    InfoBar,
+    /// The values shown in the info bar.
+    InfoValue,
+    /// The hint of a filter of the info bar while it is enabled.
+    InfoActive,
+    /// The marks of the marked commands.
+    Marked,
+    /// Errors and the deletion prompt.
+    Error,
+    /// The invalid part of the search input.
+    Invalid,
+    /// Warnings and debug values.
+    Notice,
+    /// The label of the search box and the text typed in it.
+    Prompt,
+    /// The arrow of the search box.
+    PromptArrow,
+    /// Labels and details of less importance.
+    Dim,
+    /// The command of a shell command line.
+    Command,
+    /// Options such as `-n`.
+    Flag,
+    /// Quoted strings.
+    String,
+    /// Parameter expansions and assignments.
+    Variable,
+    /// Control operators and redirections.
+    Operator,
+    /// Comments.
+    Comment,
+}
+
+impl ThemeElement {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            ThemeElement::Selection => "selection",
+            ThemeElement::Match => "match",
+            ThemeElement::InfoBar => "info_bar",
+            ThemeElement::InfoValue => "info_value",
+            ThemeElement::InfoActive => "info_active",
+            ThemeElement::Marked => "marked",
+            ThemeElement::Error => "error",
+            ThemeElement::Invalid => "invalid",
+            ThemeElement::Notice => "notice",
+            ThemeElement::Prompt => "prompt",
+            ThemeElement::PromptArrow => "prompt_arrow",
+            ThemeElement::Dim => "dim",
+            ThemeElement::Command => "command",
+            ThemeElement::Flag => "flag",
+            ThemeElement::String => "string",
+            ThemeElement::Variable => "variable",
+            ThemeElement::Operator => "operator",
+            ThemeElement::Comment => "comment",
+        }
+    }
+}
+
+impl Display for ThemeElement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+/// A terminal colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpec {
+    /// The default colour of the terminal.
+    Reset,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    DarkGray,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    White,
+    /// A colour of the 256 colour palette.
+    Indexed(u8),
+    /// A true colour.
+    Rgb(u8, u8, u8),
+}
+
+impl ColorSpec {
+    /// Parses a colour name such as `light_yellow`, a palette index such as
+    /// `208` or a true colour such as `#268bd2`.
+    pub fn parse(text: &str) -> Option<ColorSpec> {
+        if let Some(hex) = text.strip_prefix('#') {
+            let channel = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+            return match hex.len() {
+                6 => Some(ColorSpec::Rgb(channel(0)?, channel(2)?, channel(4)?)),
+                _ => None,
+            };
+        }
+        if let Ok(index) = text.parse::<u8>() {
+            return Some(ColorSpec::Indexed(index));
+        }
+        let name: String = text
+            .chars()
+            .filter(|c| !matches!(c, '_' | '-'))
+            .flat_map(char::to_lowercase)
+            .collect();
+        let color = match name.replace("grey", "gray").as_str() {
+            "reset" | "default" => ColorSpec::Reset,
+            "black" => ColorSpec::Black,
+            "red" => ColorSpec::Red,
+            "green" => ColorSpec::Green,
+            "yellow" => ColorSpec::Yellow,
+            "blue" => ColorSpec::Blue,
+            "magenta" => ColorSpec::Magenta,
+            "cyan" => ColorSpec::Cyan,
+            "gray" => ColorSpec::Gray,
+            "darkgray" => ColorSpec::DarkGray,
+            "lightred" => ColorSpec::LightRed,
+            "lightgreen" => ColorSpec::LightGreen,
+            "lightyellow" => ColorSpec::LightYellow,
+            "lightblue" => ColorSpec::LightBlue,
+            "lightmagenta" => ColorSpec::LightMagenta,
+            "lightcyan" => ColorSpec::LightCyan,
+            "white" => ColorSpec::White,
+            _ => return None,
+        };
+        Some(color)
+    }
+}
+
+/// A text attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleModifier {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    Reversed,
+}
+
+/// A style as written in the `[colors]` table, such as `"black on
+/// light_yellow bold"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyleSpec {
+    /// The text colour.
+    pub fg: Option<ColorSpec>,
+    /// The background colour.
+    pub bg: Option<ColorSpec>,
+    pub modifiers: Vec<StyleModifier>,
+}
+
+impl StyleSpec {
+    /// Parses a style: a text colour, `on` followed by a background colour,
+    /// and any of `bold`, `dim`, `italic`, `underlined` and `reversed`, in
+    /// any order. An empty style is plain text.
+    pub fn parse(text: &str) -> Result<StyleSpec, String> {
+        let mut spec = StyleSpec::default();
+        let mut words = text.split_whitespace();
+        while let Some(word) = words.next() {
+            let modifier = match word.to_lowercase().as_str() {
+                "bold" => Some(StyleModifier::Bold),
+                "dim" => Some(StyleModifier::Dim),
+                "italic" => Some(StyleModifier::Italic),
+                "underline" | "underlined" => Some(StyleModifier::Underlined),
+                "reverse" | "reversed" => Some(StyleModifier::Reversed),
+                _ => None,
+            };
+            if let Some(modifier) = modifier {
+                spec.modifiers.push(modifier);
+            } else if word.eq_ignore_ascii_case("on") {
+                let color = words.next().ok_or("`on` must be followed by a colour")?;
+                spec.bg = Some(ColorSpec::parse(color).ok_or_else(|| format!("unknown colour `{color}`"))?);
+            } else if spec.fg.is_none() {
+                spec.fg = Some(ColorSpec::parse(word).ok_or_else(|| format!("unknown colour or attribute `{word}`"))?);
+            } else {
+                return Err(format!(
+                    "`{word}`: a style has a single text colour, write `on {word}` for a background"
+                ));
+            }
+        }
+        Ok(spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!(ColorSpec::parse("light_yellow"), Some(ColorSpec::LightYellow));
+        assert_eq!(ColorSpec::parse("DarkGrey"), Some(ColorSpec::DarkGray));
+        assert_eq!(ColorSpec::parse("208"), Some(ColorSpec::Indexed(208)));
+        assert_eq!(ColorSpec::parse("#268bd2"), Some(ColorSpec::Rgb(0x26, 0x8b, 0xd2)));
+        assert_eq!(ColorSpec::parse("#268bd"), None);
+        assert_eq!(ColorSpec::parse("256"), None);
+        assert_eq!(ColorSpec::parse("mauve"), None);
+    }
+
+    #[test]
+    fn test_parse_styles() {
+        assert_eq!(
+            StyleSpec::parse("black on light_yellow bold").unwrap(),
+            StyleSpec {
+                fg: Some(ColorSpec::Black),
+                bg: Some(ColorSpec::LightYellow),
+                modifiers: vec![StyleModifier::Bold],
+            }
+        );
+        assert_eq!(
+            StyleSpec::parse("reversed on #073642").unwrap(),
+            StyleSpec {
+                fg: None,
+                bg: Some(ColorSpec::Rgb(0x07, 0x36, 0x42)),
+                modifiers: vec![StyleModifier::Reversed],
+            }
+        );
+        assert_eq!(StyleSpec::parse("").unwrap(), StyleSpec::default());
+        assert!(StyleSpec::parse("red on").is_err());
+        assert!(StyleSpec::parse("red blue").is_err());
+        assert!(StyleSpec::parse("blink").is_err());
+    }
+}
@@ -0,0 +1,634 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::search_options::{DEFAULT_HALF_LIFE_DAYS, SECS_PER_DAY};
+use crate::{
+    Action, CaseMode, ConfigOptions, Keymap, MatcherRegistry, RankingType, RushstrFiles, SearchOptions, SearchScope,
+    SearchType, SessionState, StyleSpec, ThemeElement, ThemeName, get_config_directory, parse_key,
+};
+
+/// Fewest terminal rows the UI can be drawn in.
+pub const MIN_UI_HEIGHT: u16 = 3;
+
+/// User configuration read from `config.toml` in the config directory.
+///
+/// Every key is optional, missing keys keep their default value.
+///
+/// ```toml
+/// [search]
+/// mode = "regex"
+/// cycle = ["monkey_typing", "exact", "regex", "typo", "glob"]
+/// favorites = false
+/// case = "smart"
+/// scope = "command"
+/// max_results = 500
+/// startup = "restore"
+/// restore_query = false
+/// ranking = "frecency"
+/// half_life_days = 14
+///
+/// [history]
+/// sources = ["~/.zsh_history"]
+/// ignore = ["^ls", "^cd "]
+///
+/// [ui]
+/// height = 20
+/// preview = false
+/// preview_position = "auto"
+/// theme = "default"
+///
+/// [colors]
+/// selection = "black on light_yellow bold"
+/// match = "#dc322f bold"
+///
+/// [keys]
+/// cycle_mode = ["alt-m"]
+/// copy = ["alt-c"]
+/// cancel = ["esc", "ctrl-c"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserConfig {
+    pub search: SearchConfig,
+    pub history: HistoryConfig,
+    pub ui: UiConfig,
+    /// The `[colors]` table: the styles replacing the ones of the theme.
+    pub colors: BTreeMap<ThemeElement, String>,
+    /// The `[keys]` table: the keys bound to an action, replacing its
+    /// default keys.
+    pub keys: BTreeMap<Action, Vec<String>>,
+}
+
+/// The `[search]` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Search type used at startup.
+    pub mode: SearchType,
+    /// Search types visited, in order, when switching the search type.
+    pub cycle: Vec<SearchType>,
+    /// Whether the favorites-only filter starts enabled.
+    pub favorites: bool,
+    /// Case policy of every search type.
+    pub case: CaseMode,
+    /// Text of the items searched.
+    pub scope: SearchScope,
+    /// Maximum number of results shown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<usize>,
+    /// Whether the search type and favorites filter of the previous session
+    /// are restored.
+    pub startup: StartupMode,
+    /// Whether the last query is restored too, only with `startup =
+    /// "restore"`.
+    pub restore_query: bool,
+    /// Order of the results.
+    pub ranking: RankingType,
+    /// Days after which the weight of a hit is halved when ranking.
+    pub half_life_days: u64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            mode: SearchType::MONKEY_TYPING,
+            cycle: vec![
+                SearchType::MONKEY_TYPING,
+                SearchType::EXACT,
+                SearchType::REGEX,
+                SearchType::TYPO,
+                SearchType::GLOB,
+            ],
+            favorites: false,
+            case: CaseMode::Smart,
+            scope: SearchScope::Command,
+            max_results: None,
+            startup: StartupMode::Restore,
+            restore_query: false,
+            ranking: RankingType::Frecency,
+            half_life_days: DEFAULT_HALF_LIFE_DAYS,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Returns the search type after `current` in `cycle`, or the first one
+    /// when `current` is not part of it.
+    pub fn next_search_type(&self, current: &SearchType) -> SearchType {
+        let next = match self.cycle.iter().position(|search_type| search_type == current) {
+            Some(index) => self.cycle.get(index + 1).or(self.cycle.first()),
+            None => self.cycle.first(),
+        };
+        next.cloned().unwrap_or_else(|| current.clone())
+    }
+}
+
+/// How the search settings are chosen when rushstr starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StartupMode {
+    /// Start from the settings of the previous session.
+    Restore,
+    /// Always start from the configured settings.
+    Default,
+}
+
+/// The `[history]` table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// History files to load, when empty the file of the detected shell is
+    /// used.
+    pub sources: Vec<PathBuf>,
+    /// Regular expressions, commands matching any of them are not loaded.
+    pub ignore: Vec<String>,
+}
+
+/// The `[ui]` table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// Number of terminal rows used by the UI, full screen when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u16>,
+    /// Whether the preview of the selected command starts shown.
+    pub preview: bool,
+    /// Where the preview is placed.
+    pub preview_position: PreviewPosition,
+    /// The built-in theme, before the `[colors]` overrides.
+    pub theme: ThemeName,
+}
+
+/// Where the preview of the selected command is placed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewPosition {
+    /// On the right of wide terminals, below the results otherwise.
+    #[default]
+    Auto,
+    Right,
+    Bottom,
+}
+
+/// An invalid `config.toml`, pointing at the offending key and line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// Dotted path of the offending key, e.g. `search.mode`.
+    pub key: Option<String>,
+    /// 1-based line number within the file.
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "line {}: `{key}`: {}", self.line, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl UserConfig {
+    /// Returns the file selected by `--config`, or `config.toml` inside the
+    /// config directory.
+    pub fn file_path(options: &ConfigOptions) -> anyhow::Result<PathBuf> {
+        match &options.config {
+            Some(path) => Ok(path.clone()),
+            None => Ok(get_config_directory()?.join(RushstrFiles::Config.val())),
+        }
+    }
+
+    /// Reads and validates the configuration file at `path`.
+    ///
+    /// A missing file yields the default configuration.
+    pub fn load(path: &Path) -> anyhow::Result<UserConfig> {
+        if !path.exists() {
+            return Ok(UserConfig::default());
+        }
+        let source = fs::read_to_string(path)?;
+        UserConfig::parse(&source).map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))
+    }
+
+    /// Loads the file selected by `--config` (or the default one) and
+    /// applies the command line flags on top of it.
+    pub fn from_options(options: &ConfigOptions) -> anyhow::Result<UserConfig> {
+        Ok(UserConfig::load(&UserConfig::file_path(options)?)?.merge(options))
+    }
+
+    /// Parses and validates the contents of a configuration file.
+    pub fn parse(source: &str) -> Result<UserConfig, ConfigError> {
+        let config: UserConfig = toml::from_str(source).map_err(|e| {
+            let offset = e.span().map(|span| span.start).unwrap_or(0);
+            let line = line_at(source, offset);
+            ConfigError {
+                key: key_at(source, line),
+                line,
+                message: e.message().to_string(),
+            }
+        })?;
+        config.validate(source)?;
+        Ok(config)
+    }
+
+    /// Checks the values serde cannot check by itself.
+    fn validate(&self, source: &str) -> Result<(), ConfigError> {
+        let invalid = |table: &str, key: &str, message: String| {
+            ConfigError {
+                key: Some(format!("{table}.{key}")),
+                line: line_of_key(source, table, key),
+                message,
+            }
+        };
+        if self.search.cycle.is_empty() {
+            return Err(invalid("search", "cycle", "must list at least one search type".to_string()));
+        }
+        if self.search.max_results == Some(0) {
+            return Err(invalid("search", "max_results", "must be greater than 0".to_string()));
+        }
+        if self.search.half_life_days == 0 {
+            return Err(invalid("search", "half_life_days", "must be greater than 0".to_string()));
+        }
+        if self.search.half_life_days.checked_mul(SECS_PER_DAY).is_none() {
+            let message = format!("must be at most {}", u64::MAX / SECS_PER_DAY);
+            return Err(invalid("search", "half_life_days", message));
+        }
+        if self.ui.height.is_some_and(|height| height < MIN_UI_HEIGHT) {
+            return Err(invalid("ui", "height", format!("must be at least {MIN_UI_HEIGHT}")));
+        }
+        for pattern in &self.history.ignore {
+            if let Err(e) = Regex::new(pattern) {
+                return Err(invalid("history", "ignore", e.to_string()));
+            }
+        }
+        for (element, style) in &self.colors {
+            if let Err(e) = StyleSpec::parse(style) {
+                return Err(invalid("colors", element.to_str(), e));
+            }
+        }
+        for (action, keys) in &self.keys {
+            if let Some(Err(e)) = keys.iter().map(|key| parse_key(key)).find(Result::is_err) {
+                return Err(invalid("keys", action.to_str(), e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Overrides the configured values with the flags given on the command
+    /// line.
+    pub fn merge(mut self, options: &ConfigOptions) -> UserConfig {
+        if let Some(mode) = &options.search_type {
+            self.search.mode = mode.clone();
+        }
+        if options.favorites {
+            self.search.favorites = true;
+        }
+        if let Some(case) = &options.case {
+            self.search.case = case.clone();
+        }
+        if let Some(scope) = &options.scope {
+            self.search.scope = scope.clone();
+        }
+        if let Some(ranking) = &options.ranking {
+            self.search.ranking = ranking.clone();
+        }
+        if let Some(max_results) = options.max_results {
+            self.search.max_results = Some(max_results);
+        }
+        if let Some(height) = options.height {
+            self.ui.height = Some(height);
+        }
+        self
+    }
+
+    /// Returns the keymap of the search UI.
+    pub fn keymap(&self) -> Keymap {
+        Keymap::new(&self.keys)
+    }
+
+    /// Returns the search options rushstr starts with.
+    pub fn search_options(&self) -> SearchOptions {
+        let mut options = SearchOptions {
+            search_type: self.search.mode.clone(),
+            case_mode: self.search.case.clone(),
+            scope: self.search.scope.clone(),
+            max_results: self.search.max_results,
+            ranking: self.search.ranking.clone(),
+            frecency_half_life: self.search.half_life_days.saturating_mul(SECS_PER_DAY),
+            ..SearchOptions::default()
+        };
+        options.set_favorites_only(self.search.favorites);
+        options
+    }
+
+    /// Returns the search options rushstr starts with, taking the previous
+    /// session into account.
+    ///
+    /// Flags given on the command line always win over the restored session.
+    pub fn startup_options(&self, session: Option<&SessionState>, options: &ConfigOptions) -> SearchOptions {
+        let mut search_options = self.search_options();
+        if let (StartupMode::Restore, Some(session)) = (&self.search.startup, session) {
+            search_options.search_type = session.search_type.clone();
+            search_options.set_favorites_only(session.favorites);
+            if self.search.restore_query {
+                search_options.input = session.input.clone();
+            }
+        }
+        if let Some(mode) = &options.search_type {
+            search_options.search_type = mode.clone();
+        }
+        if options.favorites {
+            search_options.set_favorites_only(true);
+        }
+        search_options
+    }
+
+    /// Checks that every configured search type has a matcher in
+    /// `matchers`.
+    pub fn check_search_types(&self, matchers: &MatcherRegistry) -> anyhow::Result<()> {
+        let known: Vec<String> = matchers.search_types().iter().map(|s| s.to_string()).collect();
+        for search_type in std::iter::once(&self.search.mode).chain(&self.search.cycle) {
+            if matchers.get(search_type).is_none() {
+                anyhow::bail!("unknown search type `{search_type}`, expected one of: {}", known.join(", "));
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the configuration back to TOML.
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+/// Returns the 1-based line that contains the byte `offset`.
+fn line_at(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// Returns the dotted key defined on `line`, prefixed by the table it lives
+/// in.
+fn key_at(source: &str, line: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let text = lines.get(line.checked_sub(1)?)?;
+    let (key, _) = text.split_once('=')?;
+    let key = key.trim();
+    let table = lines[..line - 1]
+        .iter()
+        .rev()
+        .map(|l| l.trim())
+        .find(|l| l.starts_with('[') && l.ends_with(']'))
+        .map(|l| l.trim_matches(|c| c == '[' || c == ']').trim());
+    Some(match table {
+        Some(table) => format!("{table}.{key}"),
+        None => key.to_string(),
+    })
+}
+
+/// Returns the 1-based line where `key` is assigned inside `[table]`, or
+/// the table header line if the key cannot be found.
+fn line_of_key(source: &str, table: &str, key: &str) -> usize {
+    let header = format!("[{table}]");
+    let mut table_line = 1;
+    let mut in_table = false;
+    for (index, text) in source.lines().enumerate() {
+        let text = text.trim();
+        if text.starts_with('[') {
+            in_table = text == header;
+            if in_table {
+                table_line = index + 1;
+            }
+        } else if in_table && text.split_once('=').is_some_and(|(k, _)| k.trim() == key) {
+            return index + 1;
+        }
+    }
+    table_line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config_is_default() {
+        assert_eq!(UserConfig::parse("").unwrap(), UserConfig::default());
+    }
+
+    #[test]
+    fn test_full_config() {
+        let source = r#"
+[search]
+mode = "regex"
+favorites = true
+case = "sensitive"
+scope = "raw"
+max_results = 50
+ranking = "length"
+half_life_days = 3
+
+[history]
+sources = ["/tmp/history"]
+ignore = ["^ls"]
+
+[ui]
+height = 20
+preview = true
+preview_position = "bottom"
+theme = "solarized"
+
+[colors]
+match = "magenta underlined"
+
+[keys]
+cycle_mode = ["alt-m"]
+copy = []
+"#;
+        let config = UserConfig::parse(source).unwrap();
+        assert_eq!(config.search.mode, SearchType::REGEX);
+        assert!(config.search.favorites);
+        assert_eq!(config.search.case, CaseMode::Sensitive);
+        assert_eq!(config.search_options().scope, SearchScope::Raw);
+        assert_eq!(config.search.max_results, Some(50));
+        assert_eq!(config.search.ranking, RankingType::Length);
+        assert_eq!(config.search_options().frecency_half_life, 3 * 24 * 60 * 60);
+        assert_eq!(config.history.sources, vec![PathBuf::from("/tmp/history")]);
+        assert_eq!(config.history.ignore, vec!["^ls".to_string()]);
+        assert_eq!(config.ui.height, Some(20));
+        assert!(config.ui.preview);
+        assert_eq!(config.ui.preview_position, PreviewPosition::Bottom);
+        assert_eq!(config.ui.theme, ThemeName::Solarized);
+        assert_eq!(config.colors[&ThemeElement::Match], "magenta underlined");
+        assert_eq!(config.keys[&Action::CycleMode], vec!["alt-m".to_string()]);
+        assert_eq!(config.keymap().action("alt-m"), Some(Action::CycleMode));
+        assert_eq!(config.keymap().action("ctrl-c"), None);
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        let source = "[keys]\naccept = [\"enter\"]\ncopy = [\"ctrl-y\", \"hyper-y\"]\n";
+        let error = UserConfig::parse(source).unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("keys.copy"));
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("hyper-y"));
+
+        let error = UserConfig::parse("[keys]\nexplode = [\"ctrl-e\"]\n").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn test_invalid_colors() {
+        let source = "[ui]\ntheme = \"light\"\n\n[colors]\nflag = \"cyan\"\nmatch = \"red on\"\n";
+        let error = UserConfig::parse(source).unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("colors.match"));
+        assert_eq!(error.line, 6);
+
+        let error = UserConfig::parse("[ui]\ntheme = \"neon\"\n").unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("ui.theme"));
+    }
+
+    #[test]
+    fn test_keys_round_trip() {
+        let config = UserConfig::parse("[keys]\ncycle_mode = [\"alt-m\"]\n").unwrap();
+        assert_eq!(UserConfig::parse(&config.to_toml().unwrap()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_invalid_value_names_key_and_line() {
+        let source = "[ui]\nheight = 10\n\n[search]\nranking = \"fuzzy\"\n";
+        let error = UserConfig::parse(source).unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("search.ranking"));
+        assert_eq!(error.line, 5);
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let source = "[search]\nmode = \"exact\"\ncolour = \"red\"\n";
+        let error = UserConfig::parse(source).unwrap_err();
+        assert_eq!(error.line, 3);
+        assert!(error.message.contains("colour"));
+    }
+
+    #[test]
+    fn test_semantic_validation() {
+        let source = "[history]\nsources = []\nignore = [\"(unclosed\"]\n";
+        let error = UserConfig::parse(source).unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("history.ignore"));
+        assert_eq!(error.line, 3);
+
+        let error = UserConfig::parse("[ui]\nheight = 1\n").unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("ui.height"));
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn test_half_life_overflow() {
+        let error = UserConfig::parse("[search]\nmode = \"exact\"\nhalf_life_days = 300000000000000\n").unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("search.half_life_days"));
+        assert_eq!(error.line, 3);
+
+        let longest = format!("[search]\nhalf_life_days = {}\n", u64::MAX / SECS_PER_DAY);
+        let config = UserConfig::parse(&longest).unwrap();
+        assert_eq!(
+            config.search_options().frecency_half_life,
+            u64::MAX / SECS_PER_DAY * SECS_PER_DAY
+        );
+    }
+
+    #[test]
+    fn test_cli_flags_win() {
+        let config = UserConfig::parse("[search]\nmode = \"regex\"\nmax_results = 10\n").unwrap();
+        let options = ConfigOptions {
+            search_type: Some(SearchType::EXACT),
+            favorites: true,
+            ..ConfigOptions::default()
+        };
+        let merged = config.merge(&options);
+        assert_eq!(merged.search.mode, SearchType::EXACT);
+        assert!(merged.search.favorites);
+        assert_eq!(merged.search.max_results, Some(10));
+    }
+
+    #[test]
+    fn test_cli_flags_limits() {
+        use clap::Parser;
+
+        assert!(ConfigOptions::try_parse_from(["rushstr", "--max-results", "0"]).is_err());
+        assert!(ConfigOptions::try_parse_from(["rushstr", "--height", "2"]).is_err());
+        let options = ConfigOptions::try_parse_from(["rushstr", "--max-results", "1", "--height", "3"]).unwrap();
+        let merged = UserConfig::default().merge(&options);
+        assert_eq!(merged.search.max_results, Some(1));
+        assert_eq!(merged.ui.height, Some(MIN_UI_HEIGHT));
+    }
+
+    fn session() -> SessionState {
+        SessionState {
+            search_type: SearchType::REGEX,
+            favorites: true,
+            input: "cargo".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_startup_restores_session() {
+        let config = UserConfig::parse("[search]\nmode = \"exact\"\n").unwrap();
+        let options = config.startup_options(Some(&session()), &ConfigOptions::default());
+        assert_eq!(options.search_type, SearchType::REGEX);
+        assert!(options.favorites_only());
+        assert!(options.input.is_empty());
+
+        let config = UserConfig::parse("[search]\nrestore_query = true\n").unwrap();
+        let options = config.startup_options(Some(&session()), &ConfigOptions::default());
+        assert_eq!(options.input, "cargo");
+    }
+
+    #[test]
+    fn test_startup_default_ignores_session() {
+        let config = UserConfig::parse("[search]\nmode = \"exact\"\nstartup = \"default\"\n").unwrap();
+        let options = config.startup_options(Some(&session()), &ConfigOptions::default());
+        assert_eq!(options.search_type, SearchType::EXACT);
+        assert!(!options.favorites_only());
+    }
+
+    #[test]
+    fn test_startup_cli_flags_win_over_session() {
+        let cli = ConfigOptions {
+            search_type: Some(SearchType::MONKEY_TYPING),
+            ..ConfigOptions::default()
+        };
+        let config = UserConfig::default().merge(&cli);
+        let options = config.startup_options(Some(&session()), &cli);
+        assert_eq!(options.search_type, SearchType::MONKEY_TYPING);
+        assert!(options.favorites_only());
+    }
+
+    #[test]
+    fn test_search_type_cycle() {
+        let config = UserConfig::parse("[search]\ncycle = [\"regex\", \"exact\"]").unwrap();
+        let search = &config.search;
+        assert_eq!(search.next_search_type(&SearchType::REGEX), SearchType::EXACT);
+        assert_eq!(search.next_search_type(&SearchType::EXACT), SearchType::REGEX);
+        assert_eq!(search.next_search_type(&SearchType::TYPO), SearchType::REGEX);
+
+        let error = UserConfig::parse("[search]\ncycle = []").unwrap_err();
+        assert_eq!(error.key.as_deref(), Some("search.cycle"));
+    }
+
+    #[test]
+    fn test_unknown_search_type() {
+        let config = UserConfig::parse("[search]\ncycle = [\"exact\", \"soundex\"]").unwrap();
+        let error = config.check_search_types(&MatcherRegistry::default()).unwrap_err();
+        assert!(error.to_string().contains("unknown search type `soundex`"));
+        assert!(
+            UserConfig::default()
+                .check_search_types(&MatcherRegistry::default())
+                .is_ok()
+        );
+    }
+}
@@ -1,6 +1,17 @@
-use crate::{HItem, HScanner, detect_shell, read_history};
+use std::path::PathBuf;
 
-pub struct HistoryCrawler {}
+use regex::Regex;
+
+use crate::{
+    HItem, HScanner, HistoryConfig, HistoryEntry, detect_shell, read_history, read_history_file, shell_history_file,
+};
+
+pub struct HistoryCrawler {
+    /// history files to load, empty to use the one of the detected shell
+    sources: Vec<PathBuf>,
+    /// commands matching any of these patterns are skipped
+    ignore: Vec<Regex>,
+}
 
 impl Default for HistoryCrawler {
     fn default() -> Self {
@@ -10,18 +21,62 @@ impl Default for HistoryCrawler {
 
 impl HistoryCrawler {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            sources: vec![],
+            ignore: vec![],
+        }
+    }
+
+    /// Creates a crawler for the sources and ignore patterns of the
+    /// `[history]` configuration table.
+    pub fn with_config(config: &HistoryConfig) -> anyhow::Result<Self> {
+        let ignore = config
+            .ignore
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            sources: config.sources.clone(),
+            ignore,
+        })
     }
 }
 
 impl HScanner for HistoryCrawler {
     fn load(&self) -> anyhow::Result<Vec<HItem>> {
-        let detect_shell = detect_shell();
-        let history_entries = read_history(detect_shell);
+        // the entries of every history file, with the file they come from
+        let histories: Vec<(Vec<HistoryEntry>, Option<String>)> = if self.sources.is_empty() {
+            let shell = detect_shell();
+            let source = shell_history_file(&shell).map(|file| file.display().to_string());
+            vec![(read_history(shell), source)]
+        } else {
+            // a missing source should not prevent the others from loading
+            self.sources
+                .iter()
+                .map(|source| {
+                    (
+                        read_history_file(source).unwrap_or_default(),
+                        Some(source.display().to_string()),
+                    )
+                })
+                .collect()
+        };
         let mut h_items = Vec::new();
-        for entry in history_entries {
-            let cmds = entry.split("\n").map(|m| m.to_string()).collect::<Vec<_>>();
-            h_items.push(HItem::new(cmds)?);
+        for (entries, source) in histories {
+            for entry in entries {
+                if self.ignore.iter().any(|re| re.is_match(&entry.command)) {
+                    continue;
+                }
+                let cmds = entry.command.split("\n").map(|m| m.to_string()).collect::<Vec<_>>();
+                let mut item = HItem::new(cmds)?;
+                if let Some(timestamp) = entry.timestamp {
+                    item = item.with_last_run(timestamp);
+                }
+                if let Some(source) = &source {
+                    item = item.with_source(source);
+                }
+                h_items.push(item);
+            }
         }
         // reverse to start by the most recent first
         h_items.reverse();
@@ -9,9 +9,17 @@ pub use config::args_handler::ArgsHandler;
 #[doc(inline)]
 pub use config::configuration::{ConfigOptions, RushstrFiles};
 #[doc(inline)]
+pub use config::keymap::{Action, KeyConflict, KeyUse, Keymap, key_name, parse_key};
+#[doc(inline)]
 pub use config::search_options::SearchOptions;
 #[doc(inline)]
-pub use config::search_options::SearchType;
+pub use config::search_options::{CaseMode, RankingType, SearchScope, SearchType};
+#[doc(inline)]
+pub use config::theme_config::{ColorSpec, StyleModifier, StyleSpec, ThemeElement, ThemeName};
+#[doc(inline)]
+pub use config::user_config::{
+    ConfigError, HistoryConfig, MIN_UI_HEIGHT, PreviewPosition, SearchConfig, StartupMode, UiConfig, UserConfig,
+};
 #[doc(inline)]
 pub use crawler::history_crawler::HistoryCrawler;
 #[doc(inline)]
@@ -19,6 +27,25 @@ pub use crawler::mem_crawler::MemCrawler;
 #[doc(inline)]
 pub use crawler::scanner::HScanner;
 #[doc(inline)]
+pub use stores::filters::{Filter, FilteredInput, parse_filters};
+#[doc(inline)]
+pub use stores::folding::fold;
+#[doc(inline)]
+pub use stores::learning::{LearnedChoice, LearnedChoices, query_prefixes};
+#[doc(inline)]
+pub use stores::matcher_trait::{Haystack, Matcher, MatcherRegistry, TermMatcher};
+#[doc(inline)]
+pub use stores::matchers::{
+    ExactMatcher, GlobMatcher, Highlighter, LiteralTerm, MonkeyTypingMatcher, RegexMatcher, TypoMatcher,
+};
+#[doc(inline)]
+pub use stores::query::{Query, QueryError, QueryTerm, TermKind};
+#[doc(inline)]
+pub use stores::rankers::{
+    AlphabeticalRanker, Candidate, FrecencyRanker, HitsRanker, LearnedRanker, LengthRanker, Ranker, RecencyRanker,
+    ScoreRanker, rank, ranker_for,
+};
+#[doc(inline)]
 pub use stores::store_trait::StoreTrait;
 #[doc(inline)]
 pub use stores::vector_store::VectorStore;
@@ -27,7 +54,14 @@ pub use types::common_types::{HIndex, HLines, Key, MHItem, Scanner, Shell, Store
 #[doc(inline)]
 pub use types::history_item::HItem;
 #[doc(inline)]
+pub use types::recent_query::RecentQuery;
+#[doc(inline)]
+pub use types::session_state::SessionState;
+#[doc(inline)]
+pub use utils::shell_syntax::{SyntaxKind, SyntaxToken, tokenize_shell};
+#[doc(inline)]
 pub use utils::utilities::{
-    configure_zsh_profile, create_db, delete_db, detect_shell, get_home_directory, hash_string, prepare_string,
-    print_settings, read_history,
+    HistoryEntry, RUSHSTR_HOME, configure_zsh_profile, create_db, delete_db, detect_shell, expand_tilde,
+    get_config_directory, get_data_directory, get_db_path, get_state_directory, hash_string, now_secs, prepare_string,
+    print_settings, read_history, read_history_file, reset_learning, shell_history_file,
 };
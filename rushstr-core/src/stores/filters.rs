@@ -0,0 +1,232 @@
+use std::path::Path;
+
+use crate::{HItem, QueryError, expand_tilde};
+
+/// A condition on the metadata of an item, checked before the matcher runs.
+///
+/// | Token        | Keeps items                                   |
+/// |--------------|-----------------------------------------------|
+/// | `fav:`       | marked as favorite                            |
+/// | `tag:deploy` | tagged `deploy`                               |
+/// | `dir:~/svc`  | last run inside `~/svc`                       |
+/// | `since:2d`   | used in the last 2 days (`s`, `m`, `h`, `d`, `w`) |
+/// | `exit:0`     | whose last run exited with status 0           |
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Favorite,
+    Tag(String),
+    Dir(String),
+    /// used within the given number of seconds
+    Since(u64),
+    Exit(i32),
+}
+
+impl Filter {
+    /// Returns true if `item` satisfies the filter, `now` being the current
+    /// Unix time in seconds.
+    ///
+    /// Items without the metadata a filter needs never satisfy it.
+    pub fn matches(&self, item: &HItem, now: u64) -> bool {
+        match self {
+            Filter::Favorite => item.is_fav(),
+            Filter::Tag(tag) => item.tags().iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Filter::Dir(dir) => {
+                item.dir()
+                    .is_some_and(|item_dir| Path::new(item_dir).starts_with(expand_tilde(Path::new(dir))))
+            },
+            Filter::Since(seconds) => item.last_used() > 0 && item.last_used() >= now.saturating_sub(*seconds),
+            Filter::Exit(code) => item.exit() == Some(*code),
+        }
+    }
+
+    /// Returns the metadata the filter reads when none of `items` has it.
+    ///
+    /// History files record neither the directory nor the exit status of a
+    /// command, so on a store loaded from them `dir:` and `exit:` can never
+    /// match.
+    pub fn unrecorded(&self, items: &[HItem]) -> Option<&'static str> {
+        match self {
+            Filter::Dir(_) if items.iter().all(|item| item.dir().is_none()) => Some("working directory"),
+            Filter::Exit(_) if items.iter().all(|item| item.exit().is_none()) => Some("exit status"),
+            _ => None,
+        }
+    }
+}
+
+/// A search input split into its filters and the text left for the matcher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilteredInput {
+    pub filters: Vec<Filter>,
+    /// the input with every filter replaced by spaces, so the offsets of the
+    /// remaining terms do not change
+    pub text: String,
+}
+
+/// Extracts the `key:value` filters of a search input.
+///
+/// A word made of lowercase letters followed by a colon is a filter key.
+/// A word with an unknown key, such as `nginx:latest` or `localhost:8080`,
+/// is kept as text, unless the key is one typo away from a filter key,
+/// which is an error. A leading `'` always makes a word plain text.
+pub fn parse_filters(input: &str) -> Result<FilteredInput, QueryError> {
+    let mut filters = Vec::new();
+    let mut text = input.to_string();
+    for (start, word) in words(input) {
+        let Some((key, value)) = word.split_once(':') else {
+            continue;
+        };
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_lowercase()) {
+            continue;
+        }
+        let error = |message: String| {
+            QueryError {
+                message,
+                position: Some(input[..start].chars().count()),
+            }
+        };
+        let filter = match key {
+            "fav" if value.is_empty() => Filter::Favorite,
+            "fav" => return Err(error("`fav:` takes no value".to_string())),
+            "tag" | "dir" | "since" | "exit" if value.is_empty() => {
+                return Err(error(format!("`{key}:` needs a value")));
+            },
+            "tag" => Filter::Tag(value.to_string()),
+            "dir" => Filter::Dir(value.to_string()),
+            "since" => {
+                let seconds = parse_duration(value)
+                    .ok_or_else(|| error(format!("invalid duration `{value}`, expected e.g. 30m, 2d or 1w")))?;
+                Filter::Since(seconds)
+            },
+            "exit" => {
+                let code = value
+                    .parse()
+                    .map_err(|_| error(format!("invalid exit status `{value}`")))?;
+                Filter::Exit(code)
+            },
+            _ => {
+                match FILTER_KEYS.iter().find(|known| one_edit_apart(key, known)) {
+                    Some(known) => return Err(error(format!("unknown filter `{key}:`, did you mean `{known}:`?"))),
+                    None => continue,
+                }
+            },
+        };
+        filters.push(filter);
+        text.replace_range(start..start + word.len(), &" ".repeat(word.len()));
+    }
+    Ok(FilteredInput { filters, text })
+}
+
+/// The keys of the filters.
+const FILTER_KEYS: [&str; 5] = ["fav", "tag", "dir", "since", "exit"];
+
+/// Returns true if `a` becomes `b` with a single inserted, deleted or
+/// replaced character. Both are ASCII.
+fn one_edit_apart(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if long.len() - short.len() > 1 || short == long {
+        return false;
+    }
+    let (short, long) = (short.as_bytes(), long.as_bytes());
+    let same = short.iter().zip(long).take_while(|(a, b)| a == b).count();
+    if short.len() == long.len() {
+        short[same + 1..] == long[same + 1..]
+    } else {
+        short[same..] == long[same + 1..]
+    }
+}
+
+/// Returns the whitespace separated words of `input` with their byte offset.
+fn words(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .split_whitespace()
+        .map(move |word| (word.as_ptr() as usize - input.as_ptr() as usize, word))
+}
+
+/// Parses a duration such as `90s`, `30m`, `12h`, `2d` or `1w` into seconds.
+fn parse_duration(value: &str) -> Option<u64> {
+    let unit = value.chars().last()?;
+    let amount: u64 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    amount.checked_mul(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filters() {
+        let input = "fav: since:2d exit:0 dir:~/svc deploy";
+        let parsed = parse_filters(input).unwrap();
+        assert_eq!(
+            parsed.filters,
+            [
+                Filter::Favorite,
+                Filter::Since(2 * 24 * 60 * 60),
+                Filter::Exit(0),
+                Filter::Dir("~/svc".to_string()),
+            ]
+        );
+        assert_eq!(parsed.text.trim(), "deploy");
+        assert_eq!(parsed.text.len(), input.len());
+    }
+
+    #[test]
+    fn test_plain_words_are_kept() {
+        let input = "curl localhost:8080 https://x.io 'tags:x nginx:latest colour:red tag:ops";
+        let parsed = parse_filters(input).unwrap();
+        assert_eq!(parsed.filters, [Filter::Tag("ops".to_string())]);
+        assert_eq!(
+            parsed.text.trim_end(),
+            "curl localhost:8080 https://x.io 'tags:x nginx:latest colour:red"
+        );
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        let error = parse_filters("ls tags:ops").unwrap_err();
+        assert_eq!(error.message, "unknown filter `tags:`, did you mean `tag:`?");
+        assert_eq!(error.position, Some(3));
+        assert!(parse_filters("sice:2d").is_err());
+        assert!(parse_filters("exot:0").is_err());
+        assert!(parse_filters("di:~/svc").is_err());
+        assert!(parse_filters("since:2y").is_err());
+        assert!(parse_filters("exit:").is_err());
+        assert!(parse_filters("fav:yes").is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let now = 1_000_000;
+        let mut item = HItem::new(vec!["make".to_string()])
+            .unwrap()
+            .with_dir("/home/me/svc/api")
+            .with_exit(0)
+            .with_last_run(now - 60);
+        item.add_tag("Build");
+        assert!(Filter::Tag("build".to_string()).matches(&item, now));
+        assert!(Filter::Dir("/home/me/svc".to_string()).matches(&item, now));
+        assert!(!Filter::Dir("/home/me/sv".to_string()).matches(&item, now));
+        assert!(Filter::Since(120).matches(&item, now));
+        assert!(!Filter::Since(30).matches(&item, now));
+        assert!(Filter::Exit(0).matches(&item, now));
+        assert!(!Filter::Favorite.matches(&item, now));
+
+        let bare = HItem::new(vec!["ls".to_string()]).unwrap();
+        assert!(!Filter::Exit(0).matches(&bare, now));
+        assert!(!Filter::Since(u64::MAX).matches(&bare, now));
+
+        let bare = [bare];
+        assert_eq!(Filter::Exit(0).unrecorded(&bare), Some("exit status"));
+        assert_eq!(Filter::Dir("/".to_string()).unrecorded(&bare), Some("working directory"));
+        assert_eq!(Filter::Favorite.unrecorded(&bare), None);
+        assert_eq!(Filter::Exit(0).unrecorded(&[bare[0].clone(), item]), None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::Range;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::{canonical_combining_class, is_combining_mark};
+
+/// Folds `text` into the form matchers compare.
+///
+/// Text is always normalised, so `é` typed as `e` and a combining accent
+/// equals the precomposed `é`. Ignoring case also removes diacritics:
+/// `Résumé` folds to `resume`.
+pub fn fold(text: &str, case_insensitive: bool) -> String {
+    let mut folded = String::with_capacity(text.len());
+    fold_into(text, case_insensitive, |c, _| folded.push(c));
+    folded
+}
+
+/// Same as `fold`, also returning for every character of the folded text the
+/// range of characters of `text` it comes from.
+pub fn fold_mapped(text: &str, case_insensitive: bool) -> (String, Vec<Range<usize>>) {
+    let mut folded = String::with_capacity(text.len());
+    let mut origins = Vec::with_capacity(text.len());
+    fold_into(text, case_insensitive, |c, origin| {
+        folded.push(c);
+        origins.push(origin);
+    });
+    (folded, origins)
+}
+
+/// Folds a pattern with its own syntax, such as a regular expression, which
+/// must keep its case: only normalisation and diacritics apply.
+pub fn fold_pattern(text: &str, case_insensitive: bool) -> String {
+    if text.is_ascii() {
+        text.to_string()
+    } else if case_insensitive {
+        text.nfkd().filter(|c| !is_combining_mark(*c)).collect()
+    } else {
+        text.nfc().collect()
+    }
+}
+
+/// Maps a character range of a folded text back onto the text it was folded
+/// from, using the origins returned by `fold_mapped`.
+pub fn original_range(origins: &[Range<usize>], folded: Range<usize>) -> Option<Range<usize>> {
+    if folded.is_empty() {
+        return None;
+    }
+    let start = origins.get(folded.start)?.start;
+    let end = origins.get(folded.end - 1)?.end;
+    Some(start..end)
+}
+
+/// Calls `push` with every folded character and the characters of `text` it
+/// comes from.
+fn fold_into(text: &str, case_insensitive: bool, mut push: impl FnMut(char, Range<usize>)) {
+    if text.is_ascii() {
+        for (index, c) in text.chars().enumerate() {
+            push(if case_insensitive { c.to_ascii_lowercase() } else { c }, index..index + 1);
+        }
+        return;
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut start = 0;
+    while start < chars.len() {
+        // a character and the combining marks that follow it are normalised
+        // together and map back onto each other
+        let mut end = start + 1;
+        while end < chars.len() && canonical_combining_class(chars[end]) != 0 {
+            end += 1;
+        }
+        let cluster = chars[start..end].iter().copied();
+        if case_insensitive {
+            cluster
+                .nfkd()
+                .filter(|c| !is_combining_mark(*c))
+                .flat_map(char::to_lowercase)
+                .for_each(|c| push(c, start..end));
+        } else {
+            cluster.nfc().for_each(|c| push(c, start..end));
+        }
+        start = end;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("Résumé.PDF", true), "resume.pdf");
+        assert_eq!(fold("cafe\u{301}", true), "cafe");
+        assert_eq!(fold("cafe\u{301}", false), "café");
+        assert_eq!(fold("Ångström ﬁle", true), "angstrom file");
+        assert_eq!(fold("ls -LA", true), "ls -la");
+        assert_eq!(fold("ls -LA", false), "ls -LA");
+    }
+
+    #[test]
+    fn test_fold_mapped() {
+        let (folded, origins) = fold_mapped("cafe\u{301} ﬁ", true);
+        assert_eq!(folded, "cafe fi");
+        assert_eq!(origins, [0..1, 1..2, 2..3, 3..5, 5..6, 6..7, 6..7]);
+        assert_eq!(original_range(&origins, 2..4), Some(2..5));
+        assert_eq!(original_range(&origins, 5..6), Some(6..7));
+        assert_eq!(original_range(&origins, 3..3), None);
+    }
+
+    #[test]
+    fn test_fold_pattern() {
+        assert_eq!(fold_pattern(r"R\wsum[ée]", true), r"R\wsum[ee]");
+        assert_eq!(fold_pattern("cafe\u{301}", false), "café");
+    }
+}
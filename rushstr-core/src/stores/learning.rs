@@ -0,0 +1,104 @@
+use bincode::{Decode, Encode};
+
+use crate::{Key, fold};
+
+/// Name of the database tree mapping query prefixes to the items selected
+/// for them.
+pub const LEARNING_TREE: &str = "learning";
+/// Number of query prefixes remembered, the least recently used are
+/// forgotten first.
+pub const MAX_LEARNED_PREFIXES: usize = 5_000;
+/// Number of items remembered for a prefix.
+pub const MAX_CHOICES_PER_PREFIX: usize = 8;
+/// Shortest prefix learned, a single character says too little.
+const MIN_PREFIX_CHARS: usize = 2;
+/// Longest prefix learned.
+const MAX_PREFIX_CHARS: usize = 16;
+
+/// An item selected for a query prefix, and how many times it was.
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct LearnedChoice {
+    pub key: Key,
+    pub count: u32,
+}
+
+/// The items selected for a query prefix.
+#[derive(Clone, Debug, Default, PartialEq, Encode, Decode)]
+pub struct LearnedChoices {
+    /// most selected first
+    pub choices: Vec<LearnedChoice>,
+    /// Unix time in seconds of the last selection
+    pub last_used: u64,
+}
+
+impl LearnedChoices {
+    /// Records a selection of `key` at time `now`.
+    ///
+    /// When the prefix already has `MAX_CHOICES_PER_PREFIX` items, the least
+    /// selected one makes room for `key`.
+    pub fn record(&mut self, key: Key, now: u64) {
+        match self.choices.iter_mut().find(|choice| choice.key == key) {
+            Some(choice) => choice.count = choice.count.saturating_add(1),
+            None => {
+                if self.choices.len() >= MAX_CHOICES_PER_PREFIX {
+                    self.choices.pop();
+                }
+                self.choices.push(LearnedChoice { key, count: 1 });
+            },
+        }
+        self.choices.sort_by_key(|choice| std::cmp::Reverse(choice.count));
+        self.last_used = now;
+    }
+
+    /// Returns how many times `key` was selected for the prefix.
+    pub fn count(&self, key: &Key) -> u32 {
+        self.choices
+            .iter()
+            .find(|choice| choice.key == *key)
+            .map_or(0, |choice| choice.count)
+    }
+}
+
+/// Returns the prefixes of `input` that selections are learned for, from
+/// the shortest to the longest.
+///
+/// The input is folded and its whitespace collapsed, so `Dep` and `dep`
+/// share what they learn.
+pub fn query_prefixes(input: &str) -> Vec<String> {
+    let normalized: Vec<char> = fold(&input.split_whitespace().collect::<Vec<_>>().join(" "), true)
+        .chars()
+        .collect();
+    (MIN_PREFIX_CHARS..=normalized.len().min(MAX_PREFIX_CHARS))
+        .map(|length| normalized[..length].iter().collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_prefixes() {
+        assert_eq!(query_prefixes("  Dep  p"), ["de", "dep", "dep ", "dep p"]);
+        assert!(query_prefixes("d").is_empty());
+        assert_eq!(query_prefixes(&"x".repeat(40)).len(), MAX_PREFIX_CHARS - 1);
+    }
+
+    #[test]
+    fn test_record_is_bounded() {
+        let mut learned = LearnedChoices::default();
+        for key in 0..MAX_CHOICES_PER_PREFIX as u8 {
+            learned.record([key; 32], 10);
+        }
+        learned.record([0; 32], 20);
+        assert_eq!(learned.choices[0].key, [0; 32]);
+        assert_eq!(learned.count(&[0; 32]), 2);
+        assert_eq!(learned.last_used, 20);
+
+        // a new item replaces one selected the least
+        learned.record([99; 32], 30);
+        assert_eq!(learned.choices.len(), MAX_CHOICES_PER_PREFIX);
+        assert_eq!(learned.count(&[99; 32]), 1);
+        assert_eq!(learned.count(&[0; 32]), 2);
+    }
+}
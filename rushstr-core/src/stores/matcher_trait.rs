@@ -0,0 +1,163 @@
+use std::ops::Range;
+
+use crate::stores::matchers::{ExactMatcher, GlobMatcher, MonkeyTypingMatcher, RegexMatcher, TypoMatcher};
+use crate::{Query, QueryError, QueryTerm, SearchType};
+
+/// The command of an item in the forms matchers need.
+pub struct Haystack {
+    /// the command as is
+    pub original: String,
+    /// the command normalised, and lowercased without diacritics in case
+    /// insensitive mode (see `fold`)
+    pub folded: String,
+}
+
+/// A query term compiled by a `Matcher`, run against every item.
+pub trait TermMatcher {
+    /// Returns the score of the match, higher is better, or `None` if
+    /// `haystack` does not match.
+    fn score(&self, haystack: &Haystack) -> Option<i64>;
+
+    /// Returns the character ranges of `haystack.folded` matched by the
+    /// term, empty if it does not match.
+    fn ranges(&self, haystack: &Haystack) -> Vec<Range<usize>>;
+}
+
+/// Runs the terms of a query for one `SearchType`.
+///
+/// The query syntax (negation, alternation, anchors) is shared by every
+/// matcher, a matcher only decides how each term is matched.
+pub trait Matcher {
+    /// The search type this matcher implements.
+    fn search_type(&self) -> SearchType;
+
+    /// Splits the search input into terms, with the fzf extended search
+    /// syntax unless the matcher has a syntax of its own.
+    fn parse(&self, input: &str) -> Query {
+        Query::parse(input)
+    }
+
+    /// Compiles a term of the query.
+    ///
+    /// `case_insensitive` tells whether the term should ignore case, the
+    /// term folded with `fold` compares with `Haystack::folded`. An error
+    /// position is a character index within `term.text`.
+    fn compile(&self, term: &QueryTerm, case_insensitive: bool) -> Result<Box<dyn TermMatcher>, QueryError>;
+}
+
+/// The matchers available to a store, looked up by search type.
+pub struct MatcherRegistry {
+    matchers: Vec<Box<dyn Matcher>>,
+}
+
+impl MatcherRegistry {
+    /// Creates a registry without any matcher.
+    pub fn empty() -> Self {
+        Self { matchers: Vec::new() }
+    }
+
+    /// Adds `matcher`, replacing the matcher of the same search type.
+    pub fn register(&mut self, matcher: Box<dyn Matcher>) {
+        let search_type = matcher.search_type();
+        match self.matchers.iter_mut().find(|m| m.search_type() == search_type) {
+            Some(existing) => *existing = matcher,
+            None => self.matchers.push(matcher),
+        }
+    }
+
+    /// Returns the matcher of `search_type`, if registered.
+    pub fn get(&self, search_type: &SearchType) -> Option<&dyn Matcher> {
+        self.matchers
+            .iter()
+            .find(|matcher| matcher.search_type() == *search_type)
+            .map(|matcher| matcher.as_ref())
+    }
+
+    /// Returns the registered search types, in registration order.
+    pub fn search_types(&self) -> Vec<SearchType> {
+        self.matchers.iter().map(|matcher| matcher.search_type()).collect()
+    }
+}
+
+impl Default for MatcherRegistry {
+    /// Creates a registry with the built-in matchers.
+    fn default() -> Self {
+        let mut registry = MatcherRegistry::empty();
+        registry.register(Box::new(MonkeyTypingMatcher));
+        registry.register(Box::new(ExactMatcher));
+        registry.register(Box::new(RegexMatcher));
+        registry.register(Box::new(TypoMatcher));
+        registry.register(Box::new(GlobMatcher));
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stores::matchers::filter_items;
+    use crate::{HItem, SearchOptions};
+
+    /// Matches commands containing the term written backwards.
+    struct ReverseMatcher;
+
+    struct ReverseTerm(String);
+
+    impl TermMatcher for ReverseTerm {
+        fn score(&self, haystack: &Haystack) -> Option<i64> {
+            haystack.folded.contains(&self.0).then_some(0)
+        }
+
+        fn ranges(&self, _haystack: &Haystack) -> Vec<Range<usize>> {
+            Vec::new()
+        }
+    }
+
+    impl Matcher for ReverseMatcher {
+        fn search_type(&self) -> SearchType {
+            SearchType::new("reverse")
+        }
+
+        fn compile(&self, term: &QueryTerm, _case_insensitive: bool) -> Result<Box<dyn TermMatcher>, QueryError> {
+            Ok(Box::new(ReverseTerm(term.text.chars().rev().collect())))
+        }
+    }
+
+    #[test]
+    fn test_default_registry() {
+        assert_eq!(
+            MatcherRegistry::default().search_types(),
+            [
+                SearchType::MONKEY_TYPING,
+                SearchType::EXACT,
+                SearchType::REGEX,
+                SearchType::TYPO,
+                SearchType::GLOB
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_matcher() {
+        let items = vec![
+            HItem::new(vec!["git push".to_string()]).unwrap(),
+            HItem::new(vec!["ls".to_string()]).unwrap(),
+        ];
+        let options = SearchOptions {
+            input: "hsup".to_string(),
+            search_type: SearchType::new("reverse"),
+            ..SearchOptions::default()
+        };
+        let mut registry = MatcherRegistry::default();
+        assert!(filter_items(&items, &options, &registry).is_err());
+
+        registry.register(Box::new(ReverseMatcher));
+        let found = filter_items(&items, &options, &registry).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].item.command(), "git push");
+
+        // registering a search type again replaces its matcher
+        registry.register(Box::new(ReverseMatcher));
+        assert_eq!(registry.search_types().len(), 6);
+    }
+}
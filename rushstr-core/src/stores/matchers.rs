@@ -1,98 +1,980 @@
+use std::ops::Range;
+
 use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use regex::Regex;
 
-use crate::{HItem, SearchOptions, prepare_string};
+use crate::stores::filters::{Filter, parse_filters};
+use crate::stores::folding::{fold, fold_mapped, fold_pattern, original_range};
+use crate::stores::matcher_trait::{Haystack, Matcher, MatcherRegistry, TermMatcher};
+use crate::stores::query::{Query, QueryError, QueryTerm, TermKind};
+use crate::stores::typo::{TypoMatch, find_token};
+use crate::{Candidate, HItem, SearchOptions, SearchScope, SearchType, now_secs};
 
-pub fn filter_items_monkey(items: &[HItem], options: &SearchOptions, matcher: &SkimMatcherV2) -> Vec<HItem> {
-    let input = if options.is_case_insensitive() {
-        prepare_string(&options.input).to_lowercase()
-    } else {
-        prepare_string(&options.input)
-    };
-    let reversed_input: String = input.chars().rev().collect();
+/// Matches plain terms fuzzily, like fzf.
+pub struct MonkeyTypingMatcher;
+
+impl Matcher for MonkeyTypingMatcher {
+    fn search_type(&self) -> SearchType {
+        SearchType::MONKEY_TYPING
+    }
+
+    fn compile(&self, term: &QueryTerm, case_insensitive: bool) -> Result<Box<dyn TermMatcher>, QueryError> {
+        // like fzf, a negated fuzzy term is matched exactly
+        if term.kind != TermKind::Default || term.negated {
+            return Ok(Box::new(LiteralTerm::new(term, case_insensitive)));
+        }
+        Ok(Box::new(FuzzyTerm {
+            text: fold(&term.text, case_insensitive),
+            // case is folded in the haystack and in the term, so the matcher
+            // must compare characters as they are
+            matcher: SkimMatcherV2::default().respect_case(),
+        }))
+    }
+}
+
+/// Matches plain terms as substrings.
+pub struct ExactMatcher;
+
+impl Matcher for ExactMatcher {
+    fn search_type(&self) -> SearchType {
+        SearchType::EXACT
+    }
+
+    fn compile(&self, term: &QueryTerm, case_insensitive: bool) -> Result<Box<dyn TermMatcher>, QueryError> {
+        Ok(Box::new(LiteralTerm::new(term, case_insensitive)))
+    }
+}
+
+/// Matches plain terms as regular expressions, anchors anchor the
+/// expression.
+pub struct RegexMatcher;
+
+impl Matcher for RegexMatcher {
+    fn search_type(&self) -> SearchType {
+        SearchType::REGEX
+    }
+
+    fn compile(&self, term: &QueryTerm, case_insensitive: bool) -> Result<Box<dyn TermMatcher>, QueryError> {
+        // the query took the anchor, a `$` left at the end was written `\$`
+        let text = match term.text.strip_suffix('$') {
+            Some(text) => format!("{text}\\$"),
+            None => term.text.clone(),
+        };
+        let pattern = match term.kind {
+            TermKind::Exact => return Ok(Box::new(LiteralTerm::new(term, case_insensitive))),
+            TermKind::Default => fold_pattern(&text, case_insensitive),
+            TermKind::Prefix => format!("^(?:{})", fold_pattern(&text, case_insensitive)),
+            TermKind::Suffix => format!("(?:{})$", fold_pattern(&text, case_insensitive)),
+            TermKind::Equal => format!("^(?:{})$", fold_pattern(&text, case_insensitive)),
+        };
+        // the flattened command is a single line, only raw text has several
+        let flags = if case_insensitive { "(?mi)" } else { "(?m)" };
+        match Regex::new(&format!("{flags}{pattern}")) {
+            Ok(regex) => Ok(Box::new(RegexTerm(regex))),
+            Err(error) => Err(regex_error(&text, error)),
+        }
+    }
+}
+
+/// Matches plain terms with the closest word of the command, tolerating
+/// typos.
+pub struct TypoMatcher;
+
+impl Matcher for TypoMatcher {
+    fn search_type(&self) -> SearchType {
+        SearchType::TYPO
+    }
+
+    fn compile(&self, term: &QueryTerm, case_insensitive: bool) -> Result<Box<dyn TermMatcher>, QueryError> {
+        // typos are tolerated within words, a term such as `cost$` is not one
+        let word = term.text.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if term.kind != TermKind::Default || term.negated || !word {
+            return Ok(Box::new(LiteralTerm::new(term, case_insensitive)));
+        }
+        Ok(Box::new(TypoTerm(fold(&term.text, case_insensitive).chars().collect())))
+    }
+}
+
+/// Matches the whole input as a shell glob anywhere in the command.
+///
+/// `*` matches any characters within a word, `**` crosses word boundaries,
+/// `?` matches a single character of a word and `[...]` a character class.
+pub struct GlobMatcher;
+
+impl Matcher for GlobMatcher {
+    fn search_type(&self) -> SearchType {
+        SearchType::GLOB
+    }
+
+    /// A glob is a single pattern, spaces included.
+    fn parse(&self, input: &str) -> Query {
+        let text = input.trim();
+        if text.is_empty() {
+            return Query::default();
+        }
+        let term = QueryTerm {
+            text: text.to_string(),
+            kind: TermKind::Default,
+            negated: false,
+            offset: input.len() - input.trim_start().len(),
+        };
+        Query {
+            groups: vec![vec![term]],
+        }
+    }
+
+    fn compile(&self, term: &QueryTerm, case_insensitive: bool) -> Result<Box<dyn TermMatcher>, QueryError> {
+        let pattern = glob_to_regex(&fold_pattern(&term.text, case_insensitive));
+        let pattern = if case_insensitive {
+            format!("(?i){pattern}")
+        } else {
+            pattern
+        };
+        match Regex::new(&pattern) {
+            Ok(regex) => Ok(Box::new(RegexTerm(regex))),
+            Err(error) => {
+                Err(QueryError {
+                    message: error.to_string(),
+                    position: None,
+                })
+            },
+        }
+    }
+}
+
+/// Translates a glob into an unanchored regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut pattern = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '*' if chars.get(index + 1) == Some(&'*') => {
+                pattern.push_str(".*");
+                index += 1;
+            },
+            '*' => pattern.push_str(r"\S*"),
+            '?' => pattern.push_str(r"\S"),
+            '[' => {
+                // a class needs a closing bracket and at least one character
+                let end = chars[index + 1..]
+                    .iter()
+                    .skip(1)
+                    .position(|c| *c == ']')
+                    .map(|position| index + 2 + position);
+                match end {
+                    Some(end) => {
+                        let class: String = chars[index + 1..end].iter().collect();
+                        let class = match class.strip_prefix('!') {
+                            Some(negated) => format!("^{negated}"),
+                            None => class,
+                        };
+                        pattern.push_str(&format!("[{}]", class.replace('\\', r"\\").replace('[', r"\[")));
+                        index = end;
+                    },
+                    None => pattern.push_str(r"\["),
+                }
+            },
+            c if c.is_whitespace() => {
+                while chars.get(index + 1).is_some_and(|c| c.is_whitespace()) {
+                    index += 1;
+                }
+                pattern.push_str(r"\s+");
+            },
+            c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+        index += 1;
+    }
+    pattern
+}
 
-    let mut matches: Vec<(HItem, i64)> = items
+/// A term matched literally, following its `TermKind`: a plain term is
+/// matched as a substring.
+///
+/// Matchers use it for the terms they do not handle themselves.
+pub struct LiteralTerm {
+    kind: TermKind,
+    text: String,
+}
+
+impl LiteralTerm {
+    pub fn new(term: &QueryTerm, case_insensitive: bool) -> Self {
+        Self {
+            kind: term.kind.clone(),
+            text: fold(&term.text, case_insensitive),
+        }
+    }
+}
+
+impl TermMatcher for LiteralTerm {
+    fn score(&self, haystack: &Haystack) -> Option<i64> {
+        let folded = haystack.folded.as_str();
+        let text = self.text.as_str();
+        match self.kind {
+            TermKind::Default | TermKind::Exact => folded.find(text).map(offset_score),
+            TermKind::Prefix => folded.starts_with(text).then_some(offset_score(0)),
+            TermKind::Suffix => folded.ends_with(text).then(|| offset_score(folded.len() - text.len())),
+            TermKind::Equal => (folded == text).then_some(offset_score(0)),
+        }
+    }
+
+    fn ranges(&self, haystack: &Haystack) -> Vec<Range<usize>> {
+        let folded = haystack.folded.as_str();
+        let text = self.text.as_str();
+        let length = folded.chars().count();
+        let text_length = text.chars().count();
+        match self.kind {
+            TermKind::Default | TermKind::Exact => {
+                folded
+                    .match_indices(text)
+                    .map(|(start, found)| char_range(folded, start..start + found.len()))
+                    .collect()
+            },
+            TermKind::Prefix => folded.starts_with(text).then_some(0..text_length).into_iter().collect(),
+            TermKind::Suffix => {
+                let found = folded.ends_with(text).then(|| length - text_length..length);
+                found.into_iter().collect()
+            },
+            TermKind::Equal => (folded == text).then_some(0..length).into_iter().collect(),
+        }
+    }
+}
+
+/// A fuzzy term.
+struct FuzzyTerm {
+    text: String,
+    matcher: SkimMatcherV2,
+}
+
+impl TermMatcher for FuzzyTerm {
+    fn score(&self, haystack: &Haystack) -> Option<i64> {
+        self.matcher.fuzzy_match(&haystack.folded, &self.text)
+    }
+
+    fn ranges(&self, haystack: &Haystack) -> Vec<Range<usize>> {
+        self.matcher
+            .fuzzy_indices(&haystack.folded, &self.text)
+            .map(|(_, indices)| indices.into_iter().map(|index| index..index + 1).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A regular expression, case is handled by the expression itself.
+///
+/// It runs on the folded command, diacritics and normalisation being folded
+/// in the expression too.
+struct RegexTerm(Regex);
+
+impl TermMatcher for RegexTerm {
+    fn score(&self, haystack: &Haystack) -> Option<i64> {
+        self.0.find(&haystack.folded).map(|found| offset_score(found.start()))
+    }
+
+    fn ranges(&self, haystack: &Haystack) -> Vec<Range<usize>> {
+        self.0
+            .find_iter(&haystack.folded)
+            .map(|found| char_range(&haystack.folded, found.range()))
+            .collect()
+    }
+}
+
+/// A typo tolerant term.
+struct TypoTerm(Vec<char>);
+
+impl TermMatcher for TypoTerm {
+    fn score(&self, haystack: &Haystack) -> Option<i64> {
+        find_token(&self.0, &haystack.folded).map(|found| typo_score(&found))
+    }
+
+    fn ranges(&self, haystack: &Haystack) -> Vec<Range<usize>> {
+        find_token(&self.0, &haystack.folded)
+            .map(|found| found.range)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// A compiled `QueryTerm`.
+struct CompiledTerm {
+    matcher: Box<dyn TermMatcher>,
+    negated: bool,
+}
+
+/// A search input ready to run against the items.
+#[derive(Default)]
+struct CompiledQuery {
+    /// the filters of the options and of the input
+    filters: Vec<Filter>,
+    /// every group must match, a group matches when any of its terms does
+    groups: Vec<Vec<CompiledTerm>>,
+}
+
+/// Extracts the filters of `options.input` and compiles the remaining terms
+/// with the matcher of `options.search_type`.
+fn compile_query(options: &SearchOptions, matchers: &MatcherRegistry) -> Result<CompiledQuery, QueryError> {
+    let input = parse_filters(&options.input)?;
+    let mut filters = options.filters.clone();
+    filters.extend(input.filters);
+    Ok(CompiledQuery {
+        filters,
+        groups: compile_terms(&input.text, options, matchers)?,
+    })
+}
+
+/// Compiles every term of `input` with the matcher of `options.search_type`.
+fn compile_terms(
+    input: &str,
+    options: &SearchOptions,
+    matchers: &MatcherRegistry,
+) -> Result<Vec<Vec<CompiledTerm>>, QueryError> {
+    let Some(matcher) = matchers.get(&options.search_type) else {
+        return Err(QueryError {
+            message: format!("unknown search type `{}`", options.search_type),
+            position: None,
+        });
+    };
+    let case_insensitive = options.is_case_insensitive();
+    matcher
+        .parse(input)
+        .groups
         .iter()
-        .filter_map(|item| {
-            if options.favorites && !item.is_fav() {
-                return None;
+        .map(|group| {
+            group
+                .iter()
+                .map(|term| {
+                    let compiled = matcher.compile(term, case_insensitive).map_err(|error| {
+                        // the matcher locates the error within the term
+                        let before = input.get(..term.offset).map(|before| before.chars().count());
+                        QueryError {
+                            position: error.position.zip(before).map(|(position, before)| before + position),
+                            ..error
+                        }
+                    })?;
+                    Ok(CompiledTerm {
+                        matcher: compiled,
+                        negated: term.negated,
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Describes why the regular expression `text` is invalid, locating the
+/// error within it when possible.
+fn regex_error(text: &str, error: regex::Error) -> QueryError {
+    match regex_syntax::ast::parse::Parser::new().parse(text) {
+        Err(syntax) => {
+            let offset = syntax.span().start.offset.min(text.len());
+            QueryError {
+                message: syntax.kind().to_string(),
+                position: text.get(..offset).map(|before| before.chars().count()),
             }
-            let target = if options.is_case_insensitive() {
-                item.command().to_lowercase()
-            } else {
-                item.command()
-            };
+        },
+        // the syntax is fine, the expression failed later (e.g. too big)
+        Ok(_) => {
+            QueryError {
+                message: error.to_string(),
+                position: None,
+            }
+        },
+    }
+}
 
-            let forward_score = matcher.fuzzy_match(&target, &input);
-            let backward_score = matcher.fuzzy_match(&target, &reversed_input);
+/// Builds the haystack of `item` for the scope and case policy of
+/// `options`.
+fn haystack(item: &HItem, options: &SearchOptions) -> Haystack {
+    let original = options.searched_text(item);
+    let folded = fold(&original, options.is_case_insensitive());
+    Haystack { original, folded }
+}
 
-            match (forward_score, backward_score) {
-                (Some(f), Some(b)) => Some((item.clone(), f.max(b))),
-                (Some(f), None) => Some((item.clone(), f)),
-                (None, Some(b)) => Some((item.clone(), b)),
-                (None, None) => None,
-            }
-        })
-        .collect();
-    matches.sort_by(|a, b| {
-        b.0.hits()
-            .cmp(&a.0.hits()) // order by hits first
-            .then(b.1.cmp(&a.1)) // then order by score
-    });
-    matches.into_iter().map(|(item, _score)| item).collect()
-}
-
-pub fn filter_items_exact(items: &[HItem], options: &SearchOptions) -> Vec<HItem> {
-    let input = if options.is_case_insensitive() {
-        options.input.to_lowercase()
-    } else {
-        options.input.to_string()
-    };
+/// Returns the score of `haystack` against the query, or `None` if a group
+/// does not match.
+///
+/// The score is the sum of the best score of every group, negated terms do
+/// not add to it.
+fn score_query(groups: &[Vec<CompiledTerm>], haystack: &Haystack) -> Option<i64> {
+    let mut total = 0;
+    for group in groups {
+        let best = group
+            .iter()
+            .filter_map(|term| {
+                match (term.matcher.score(haystack), term.negated) {
+                    (Some(score), false) => Some(score),
+                    (None, true) => Some(0),
+                    _ => None,
+                }
+            })
+            .max()?;
+        total += best;
+    }
+    Some(total)
+}
+
+/// Returns the items matching `options.input`, parsed with the fzf extended
+/// search syntax (see `Query`).
+///
+/// Items must satisfy every filter (see `Filter`) before the matcher runs on
+/// the rest of the input. They are returned in their original order,
+/// ranking happens afterwards.
+/// A query the matcher cannot run, such as an invalid regular expression,
+/// or a filter on metadata none of the items has, is reported as a
+/// `QueryError`.
+pub fn filter_items(
+    items: &[HItem],
+    options: &SearchOptions,
+    matchers: &MatcherRegistry,
+) -> Result<Vec<Candidate>, QueryError> {
+    let query = compile_query(options, matchers)?;
+    if let Some(metadata) = query.filters.iter().find_map(|filter| filter.unrecorded(items)) {
+        return Err(QueryError {
+            message: format!("no {metadata} recorded, rushstr does not collect it yet"),
+            position: None,
+        });
+    }
+    let now = now_secs();
 
-    items
+    let candidates = items
         .iter()
-        .filter_map(|item| {
-            if options.favorites && !item.is_fav() {
+        .enumerate()
+        .filter_map(|(position, item)| {
+            if !query.filters.iter().all(|filter| filter.matches(item, now)) {
                 return None;
             }
-            let haystack = if options.is_case_insensitive() {
-                item.command().to_lowercase()
+            let score = if query.groups.is_empty() {
+                0
             } else {
-                item.command()
+                score_query(&query.groups, &haystack(item, options))?
             };
-            if haystack.contains(&input) {
-                Some(item.clone())
-            } else {
-                None
-            }
+            Some(Candidate::new(item.clone(), score, position))
         })
-        .collect()
+        .collect();
+    Ok(candidates)
 }
 
-pub fn filter_items_regex(items: &[HItem], options: &SearchOptions) -> Vec<HItem> {
-    let pattern = if options.is_case_insensitive() {
-        format!("(?i){}", options.input)
-    } else {
-        options.input.clone()
-    };
+/// Finds the parts of items matched by a query, for highlighting.
+///
+/// The query is compiled once, so a single highlighter can be used for
+/// every visible item.
+pub struct Highlighter {
+    options: SearchOptions,
+    groups: Vec<Vec<CompiledTerm>>,
+}
 
-    let re = match Regex::new(&pattern) {
-        Ok(re) => re,
-        Err(_) => return vec![], // return empty if the regex is invalid
-    };
+impl Highlighter {
+    /// Creates a highlighter for the query in `options.input`, an invalid
+    /// query highlights nothing.
+    pub fn new(options: &SearchOptions, matchers: &MatcherRegistry) -> Self {
+        Self {
+            options: options.clone(),
+            groups: compile_query(options, matchers).unwrap_or_default().groups,
+        }
+    }
+
+    /// Returns the sorted, non-overlapping character ranges of the searched
+    /// text of `item` (see `SearchOptions::searched_text`) matched by the
+    /// query.
+    pub fn ranges(&self, item: &HItem) -> Vec<Range<usize>> {
+        let original = self.options.searched_text(item);
+        let (folded, origins) = fold_mapped(&original, self.options.is_case_insensitive());
+        let haystack = Haystack { original, folded };
+        // terms match the folded command, which may differ in length
+        let mut ranges: Vec<Range<usize>> = self
+            .groups
+            .iter()
+            .flatten()
+            .filter(|term| !term.negated)
+            .flat_map(|term| term.matcher.ranges(&haystack))
+            .filter_map(|range| original_range(&origins, range))
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        merge_ranges(ranges)
+    }
+
+    /// Returns the matched character ranges of every line in
+    /// `item.command_lines()`.
+    pub fn line_ranges(&self, item: &HItem) -> Vec<Vec<Range<usize>>> {
+        let positions = match self.options.scope {
+            SearchScope::Command => item.command_positions(),
+            SearchScope::Raw => item.raw_positions(),
+        };
+        let mut lines = vec![Vec::new(); item.hlines()];
+        for range in self.ranges(item) {
+            for (line, index) in range.filter_map(|index| positions.get(index).copied().flatten()) {
+                lines[line].push(index..index + 1);
+            }
+        }
+        lines.into_iter().map(merge_ranges).collect()
+    }
+}
+
+/// Merges sorted ranges that overlap or touch.
+fn merge_ranges(ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Converts a byte range of `text` into a character range.
+fn char_range(text: &str, bytes: Range<usize>) -> Range<usize> {
+    let start = text[..bytes.start].chars().count();
+    start..start + text[bytes].chars().count()
+}
+
+/// Scores a substring match, the closer to the start of the command the
+/// higher the score.
+fn offset_score(start: usize) -> i64 {
+    -(start as i64)
+}
+
+/// Scores a typo match, fewer edits first, then tokens closest in length to
+/// the term.
+fn typo_score(found: &TypoMatch) -> i64 {
+    100 * (3 - found.distance as i64) - found.extra as i64
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
+mod tests {
+    use super::*;
+    use crate::CaseMode;
 
-    items
+    fn items() -> Vec<HItem> {
+        [
+            "docker compose up -d",
+            "docker ps",
+            "sudo docker ps",
+            "git push origin main",
+            "cargo build --release",
+            "ls",
+            "ls -la",
+        ]
         .iter()
-        .filter_map(|item| {
-            if options.favorites && !item.is_fav() {
-                return None;
+        .map(|command| HItem::new(vec![command.to_string()]).unwrap())
+        .collect()
+    }
+
+    fn search(input: &str, search_type: SearchType) -> Vec<String> {
+        search_case(input, search_type, CaseMode::Smart)
+    }
+
+    fn search_case(input: &str, search_type: SearchType, case_mode: CaseMode) -> Vec<String> {
+        let options = SearchOptions {
+            input: input.to_string(),
+            search_type,
+            case_mode,
+            ..SearchOptions::default()
+        };
+        filter_items(&items(), &options, &MatcherRegistry::default())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|candidate| candidate.item.command())
+            .collect()
+    }
+
+    #[test]
+    fn test_monkey_and_negation() {
+        assert_eq!(search("docker !compose !^sudo", SearchType::MONKEY_TYPING), ["docker ps"]);
+        assert_eq!(search("dkr 'ps", SearchType::MONKEY_TYPING), ["docker ps", "sudo docker ps"]);
+    }
+
+    #[test]
+    fn test_case_modes_in_every_mode() {
+        for search_type in [
+            SearchType::MONKEY_TYPING,
+            SearchType::EXACT,
+            SearchType::REGEX,
+            SearchType::TYPO,
+        ] {
+            assert_eq!(search_case("^LS -LA$", search_type.clone(), CaseMode::Insensitive), ["ls -la"]);
+            assert!(search_case("^LS -LA$", search_type.clone(), CaseMode::Smart).is_empty());
+            assert!(search_case("^LS -LA$", search_type.clone(), CaseMode::Sensitive).is_empty());
+            assert_eq!(search_case("^ls$", search_type.clone(), CaseMode::Sensitive), ["ls"]);
+        }
+        let items = vec![HItem::new(vec!["Make Test".to_string()]).unwrap()];
+        let options = |case_mode| {
+            SearchOptions {
+                input: "mt".to_string(),
+                case_mode,
+                ..SearchOptions::default()
             }
-            if re.is_match(&item.command()) {
-                Some(item.clone())
-            } else {
-                None
+        };
+        assert_eq!(
+            filter_items(&items, &options(CaseMode::Smart), &MatcherRegistry::default())
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(
+            filter_items(&items, &options(CaseMode::Sensitive), &MatcherRegistry::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_smart_case_ignores_filters_and_escapes() {
+        let options = |input: &str, search_type: SearchType| {
+            SearchOptions {
+                input: input.to_string(),
+                search_type,
+                case_mode: CaseMode::Smart,
+                ..SearchOptions::default()
             }
-        })
-        .collect()
+        };
+        assert!(options(r"\S+foo", SearchType::REGEX).is_case_insensitive());
+        assert!(options(r"\W\D\B\Afoo", SearchType::REGEX).is_case_insensitive());
+        assert!(!options(r"\S+Foo", SearchType::REGEX).is_case_insensitive());
+        assert!(!options(r"\S+foo", SearchType::EXACT).is_case_insensitive());
+        assert!(options("tag:Build dir:~/Projects git", SearchType::MONKEY_TYPING).is_case_insensitive());
+
+        let mut item = HItem::new(vec!["GIT push".to_string()]).unwrap();
+        item.add_tag("Build");
+        let found = filter_items(
+            &[item],
+            &options("tag:Build git", SearchType::EXACT),
+            &MatcherRegistry::default(),
+        )
+        .unwrap();
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn test_escaped_dollar_in_every_mode() {
+        let items: Vec<HItem> = ["echo cost$", "echo cost", "echo costs"]
+            .iter()
+            .map(|command| HItem::new(vec![command.to_string()]).unwrap())
+            .collect();
+        let search = |input: &str, search_type: SearchType| -> Vec<String> {
+            let options = SearchOptions {
+                input: input.to_string(),
+                search_type,
+                ..SearchOptions::default()
+            };
+            filter_items(&items, &options, &MatcherRegistry::default())
+                .unwrap()
+                .into_iter()
+                .map(|candidate| candidate.item.command())
+                .collect()
+        };
+        for search_type in [
+            SearchType::MONKEY_TYPING,
+            SearchType::EXACT,
+            SearchType::REGEX,
+            SearchType::TYPO,
+        ] {
+            assert_eq!(search(r"cost\$", search_type.clone()), ["echo cost$"], "{search_type:?}");
+        }
+        // a glob has no anchors, `$` is plain text
+        assert_eq!(search("cost$", SearchType::GLOB), ["echo cost$"]);
+    }
+
+    #[test]
+    fn test_anchors_in_every_mode() {
+        for search_type in [
+            SearchType::MONKEY_TYPING,
+            SearchType::EXACT,
+            SearchType::REGEX,
+            SearchType::TYPO,
+        ] {
+            assert_eq!(search("^ls$", search_type.clone()), ["ls"]);
+            assert_eq!(search("^sudo", search_type.clone()), ["sudo docker ps"]);
+            assert_eq!(search("ps$", search_type.clone()), ["docker ps", "sudo docker ps"]);
+        }
+    }
+
+    #[test]
+    fn test_typo_tolerance() {
+        assert_eq!(search("gti psuh", SearchType::TYPO), ["git push origin main"]);
+        assert_eq!(search("dokcer ps !sudo", SearchType::TYPO), ["docker ps"]);
+        assert_eq!(search("relaese", SearchType::TYPO), ["cargo build --release"]);
+        assert!(search("psuh", SearchType::MONKEY_TYPING).is_empty());
+    }
+
+    /// Run with `cargo test --release -p rushstr-core -- --ignored
+    /// --nocapture typo_speed`.
+    #[test]
+    #[ignore = "timing, run in release mode"]
+    fn test_typo_speed_on_100k_items() {
+        let tools = ["kubectl", "docker", "git", "cargo", "terraform", "ssh", "make", "npm"];
+        let verbs = ["get", "apply", "push", "build", "describe", "logs", "run", "status"];
+        let items: Vec<HItem> = (0..100_000)
+            .map(|index| {
+                let command = format!(
+                    "{} {} --namespace team-{} service-{index} -o wide",
+                    tools[index % tools.len()],
+                    verbs[index / tools.len() % verbs.len()],
+                    index % 97,
+                );
+                HItem::new(vec![command]).unwrap()
+            })
+            .collect();
+        let options = SearchOptions {
+            input: "kubeclt".to_string(),
+            search_type: SearchType::TYPO,
+            ..SearchOptions::default()
+        };
+        let start = std::time::Instant::now();
+        let found = filter_items(&items, &options, &MatcherRegistry::default()).unwrap();
+        let elapsed = start.elapsed();
+        println!("typo search of 100k items: {elapsed:?}");
+        assert_eq!(found.len(), 100_000 / tools.len());
+        assert!(elapsed < std::time::Duration::from_millis(500), "took {elapsed:?}");
+    }
+
+    #[test]
+    fn test_filters_before_matcher() {
+        let mut items = items();
+        items[1].flip_fav();
+        items[2].flip_fav();
+        let options = SearchOptions {
+            input: "fav: !sudo".to_string(),
+            ..SearchOptions::default()
+        };
+        let found = filter_items(&items, &options, &MatcherRegistry::default()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].item.command(), "docker ps");
+
+        let mut options = SearchOptions {
+            input: "dkr".to_string(),
+            ..SearchOptions::default()
+        };
+        options.set_favorites_only(true);
+        assert_eq!(
+            filter_items(&items, &options, &MatcherRegistry::default())
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let options = SearchOptions {
+            input: "[ tags:red".to_string(),
+            search_type: SearchType::REGEX,
+            ..SearchOptions::default()
+        };
+        let error = filter_items(&items, &options, &MatcherRegistry::default()).unwrap_err();
+        assert_eq!(error.message, "unknown filter `tags:`, did you mean `tag:`?");
+
+        let options = SearchOptions {
+            input: "exit:0 docker".to_string(),
+            ..SearchOptions::default()
+        };
+        let error = filter_items(&items, &options, &MatcherRegistry::default()).unwrap_err();
+        assert_eq!(error.message, "no exit status recorded, rushstr does not collect it yet");
+        items[0] = items[0].clone().with_exit(0);
+        assert!(filter_items(&items, &options, &MatcherRegistry::default()).is_ok());
+    }
+
+    #[test]
+    fn test_alternation() {
+        assert_eq!(
+            search("^git | ^cargo", SearchType::EXACT),
+            ["git push origin main", "cargo build --release"]
+        );
+        assert_eq!(
+            search("'main | release push", SearchType::MONKEY_TYPING),
+            ["git push origin main"]
+        );
+    }
+
+    #[test]
+    fn test_exact_and_regex_terms() {
+        assert_eq!(search("'ls -la", SearchType::MONKEY_TYPING), ["ls -la"]);
+        assert_eq!(
+            search(r"^(git|cargo) \S+$", SearchType::REGEX),
+            ["git push origin main", "cargo build --release"]
+        );
+        assert_eq!(search(r"^(git|cargo) --release$", SearchType::REGEX), ["cargo build --release"]);
+    }
+
+    #[test]
+    fn test_regex_error_position() {
+        let options = SearchOptions {
+            input: "docker ^(ps".to_string(),
+            search_type: SearchType::REGEX,
+            ..SearchOptions::default()
+        };
+        let error = filter_items(&items(), &options, &MatcherRegistry::default()).unwrap_err();
+        assert_eq!(error.position, Some(8));
+        assert!(error.message.contains("unclosed group"));
+
+        let options = SearchOptions {
+            input: "é a{2,1}".to_string(),
+            ..options
+        };
+        let error = filter_items(&items(), &options, &MatcherRegistry::default()).unwrap_err();
+        assert_eq!(error.position, Some(3));
+    }
+
+    #[test]
+    fn test_accents_in_every_mode() {
+        let items: Vec<HItem> = ["vim résumé.md", "open cafe\u{301}.txt", "echo Ångström"]
+            .iter()
+            .map(|command| HItem::new(vec![command.to_string()]).unwrap())
+            .collect();
+        let search = |input: &str, search_type: SearchType| -> usize {
+            let options = SearchOptions {
+                input: input.to_string(),
+                search_type,
+                ..SearchOptions::default()
+            };
+            filter_items(&items, &options, &MatcherRegistry::default())
+                .unwrap()
+                .len()
+        };
+        for search_type in MatcherRegistry::default().search_types() {
+            assert_eq!(search("resume", search_type.clone()), 1, "{search_type}");
+            assert_eq!(search("café", search_type.clone()), 1, "{search_type}");
+            assert_eq!(search("angstrom", search_type.clone()), 1, "{search_type}");
+        }
+        // uppercase turns smart case sensitive, accents then count
+        assert_eq!(search("Angstrom", SearchType::EXACT), 0);
+        assert_eq!(search("Ångström", SearchType::EXACT), 1);
+        // combining accents equal precomposed ones in sensitive mode too
+        assert_eq!(search("Open café", SearchType::EXACT), 0);
+        assert_eq!(search("^open caf\u{e9}", SearchType::REGEX), 1);
+    }
+
+    #[test]
+    fn test_highlight_folded_offsets() {
+        assert_eq!(highlight("resume", SearchType::EXACT, &["vim résumé.md"]), [vec![4..10]]);
+        // the combining accent is highlighted with its letter
+        assert_eq!(highlight("cafe", SearchType::REGEX, &["open cafe\u{301}.txt"]), [vec![5..10]]);
+        assert_eq!(highlight("file", SearchType::EXACT, &["ﬁle"]), [vec![0..3]]);
+    }
+
+    fn highlight(input: &str, search_type: SearchType, command: &[&str]) -> Vec<Vec<Range<usize>>> {
+        let options = SearchOptions {
+            input: input.to_string(),
+            search_type,
+            ..SearchOptions::default()
+        };
+        let item = HItem::new(command.iter().map(|line| line.to_string()).collect()).unwrap();
+        Highlighter::new(&options, &MatcherRegistry::default()).line_ranges(&item)
+    }
+
+    #[test]
+    fn test_highlight_fuzzy_indices() {
+        // only the characters the fuzzy matcher used, not every g, i and t
+        assert_eq!(highlight("git", SearchType::MONKEY_TYPING, &["git commit"]), [vec![0..3]]);
+    }
+
+    #[test]
+    fn test_highlight_exact_spans() {
+        assert_eq!(highlight("ls", SearchType::EXACT, &["ls -la && ls"]), [vec![0..2, 10..12]]);
+        assert_eq!(highlight("!ls ^ls", SearchType::EXACT, &["ls"]), [vec![0..2]]);
+    }
+
+    #[test]
+    fn test_highlight_typo_tokens() {
+        assert_eq!(
+            highlight("kubeclt gte", SearchType::TYPO, &["kubectl get pods"]),
+            [vec![0..7, 8..11]]
+        );
+    }
+
+    #[test]
+    fn test_highlight_regex_spans() {
+        assert_eq!(highlight(r"o\w", SearchType::REGEX, &["go to foo"]), [vec![7..9]]);
+        assert!(highlight("(unclosed", SearchType::REGEX, &["(unclosed"])[0].is_empty());
+    }
+
+    #[test]
+    fn test_highlight_multi_line() {
+        let ranges = highlight("'PATH", SearchType::EXACT, &["export  A=1", "  export PATH=$PATH"]);
+        assert_eq!(ranges, [vec![], vec![9..13, 15..19]]);
+    }
+
+    #[test]
+    fn test_raw_scope() {
+        let items = vec![
+            HItem::new(vec!["psql <<SQL".to_string(), "  SELECT 1;".to_string(), "SQL".to_string()]).unwrap(),
+            HItem::new(vec!["echo SELECT 1".to_string()]).unwrap(),
+        ];
+        let search = |input: &str, search_type: SearchType, scope: SearchScope| -> usize {
+            let options = SearchOptions {
+                input: input.to_string(),
+                search_type,
+                scope,
+                ..SearchOptions::default()
+            };
+            filter_items(&items, &options, &MatcherRegistry::default())
+                .unwrap()
+                .len()
+        };
+        assert_eq!(search(r"^\s+select", SearchType::REGEX, SearchScope::Command), 0);
+        assert_eq!(search(r"^\s+select", SearchType::REGEX, SearchScope::Raw), 1);
+        assert_eq!(search("^sql$", SearchType::REGEX, SearchScope::Raw), 1);
+        assert_eq!(search(r"'\ \ select", SearchType::EXACT, SearchScope::Command), 0);
+        assert_eq!(search(r"'\ \ select", SearchType::EXACT, SearchScope::Raw), 1);
+        assert_eq!(search("select", SearchType::MONKEY_TYPING, SearchScope::Raw), 2);
+    }
+
+    #[test]
+    fn test_highlight_raw_lines() {
+        let options = SearchOptions {
+            input: r"^\s+\w+".to_string(),
+            search_type: SearchType::REGEX,
+            scope: SearchScope::Raw,
+            ..SearchOptions::default()
+        };
+        let item = HItem::new(vec!["cat <<EOF".to_string(), "    x y".to_string(), "EOF".to_string()]).unwrap();
+        let ranges = Highlighter::new(&options, &MatcherRegistry::default()).line_ranges(&item);
+        assert_eq!(ranges, [vec![], vec![0..5], vec![]]);
+    }
+
+    #[test]
+    fn test_glob_to_regex() {
+        assert_eq!(glob_to_regex("git ?ommit"), r"git\s+\Sommit");
+        assert_eq!(glob_to_regex("a**b*c"), r"a.*b\S*c");
+        assert_eq!(glob_to_regex("[!a-c]x[]"), r"[^a-c]x\[\]");
+        // a leading bracket belongs to the class
+        assert_eq!(glob_to_regex("[]]"), "[]]");
+        assert_eq!(glob_to_regex("1.2$"), r"1\.2\$");
+    }
+
+    #[test]
+    fn test_glob() {
+        let items = |commands: &[&str]| -> Vec<HItem> {
+            commands
+                .iter()
+                .map(|c| HItem::new(vec![c.to_string()]).unwrap())
+                .collect()
+        };
+        let commands = items(&[
+            "docker run -p 8080:80 nginx",
+            "docker run --rm -p 80:80 nginx",
+            "git commit -m fix",
+            "git ommit",
+            "cat src/main.rs",
+        ]);
+        let glob = |input: &str| -> Vec<String> {
+            let options = SearchOptions {
+                input: input.to_string(),
+                search_type: SearchType::GLOB,
+                ..SearchOptions::default()
+            };
+            filter_items(&commands, &options, &MatcherRegistry::default())
+                .unwrap()
+                .into_iter()
+                .map(|candidate| candidate.item.command())
+                .collect()
+        };
+        assert_eq!(glob("docker * -p 80*"), ["docker run -p 8080:80 nginx"]);
+        assert_eq!(
+            glob("docker ** -p 80*"),
+            ["docker run -p 8080:80 nginx", "docker run --rm -p 80:80 nginx"]
+        );
+        assert_eq!(glob("git ?ommit"), ["git commit -m fix"]);
+        assert_eq!(glob("cat *.rs"), ["cat src/main.rs"]);
+        assert_eq!(glob("GIT C*"), Vec::<String>::new());
+        assert_eq!(highlight("m?in", SearchType::GLOB, &["cat src/main.rs"]), [vec![8..12]]);
+    }
 }
@@ -1,3 +1,10 @@
+pub mod filters;
+pub mod folding;
+pub mod learning;
+pub mod matcher_trait;
 pub mod matchers;
+pub mod query;
+pub mod rankers;
 pub mod store_trait;
+pub mod typo;
 pub mod vector_store;
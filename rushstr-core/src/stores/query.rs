@@ -0,0 +1,259 @@
+use std::fmt::{Display, Formatter};
+
+/// How a single query term is matched against a command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TermKind {
+    /// A plain term, matched the way the active `SearchType` matches.
+    Default,
+    /// `'term`, the command contains the term.
+    Exact,
+    /// `^term`, the command starts with the term.
+    Prefix,
+    /// `term$`, the command ends with the term.
+    Suffix,
+    /// `^term$`, the command is the term.
+    Equal,
+}
+
+/// One term of a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryTerm {
+    /// The term without its operators, with `\$` unescaped.
+    pub text: String,
+    pub kind: TermKind,
+    /// `!term`, the command must not match the term.
+    pub negated: bool,
+    /// Byte offset of `text` within the input.
+    pub offset: usize,
+}
+
+/// A query that cannot be run, such as an invalid regular expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    /// Character index within the input where the error is, if known.
+    pub position: Option<usize>,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} (at {position})", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A query written with the fzf extended search syntax.
+///
+/// | Token       | Match type                      |
+/// |-------------|---------------------------------|
+/// | `sbtrkt`    | matched by the active search type |
+/// | `'wild`     | contains `wild`                 |
+/// | `^music`    | starts with `music`             |
+/// | `.mp3$`     | ends with `.mp3`                |
+/// | `^ls$`      | is exactly `ls`                 |
+/// | `!fire`     | does not match `fire`           |
+/// | `a \| b`    | matches `a` or `b`              |
+///
+/// Terms separated by spaces must all match, `\ ` keeps a space inside a
+/// term.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    /// Every group must match, a group matches when any of its terms does.
+    pub groups: Vec<Vec<QueryTerm>>,
+}
+
+impl Query {
+    /// Parses `input` into a query, an input without terms matches
+    /// everything.
+    pub fn parse(input: &str) -> Query {
+        let mut groups: Vec<Vec<QueryTerm>> = Vec::new();
+        let mut alternate = false;
+        for (offset, token) in tokenize(input) {
+            if token == "|" {
+                alternate = !groups.is_empty();
+                continue;
+            }
+            let Some(term) = parse_term(&token, offset) else {
+                continue;
+            };
+            match groups.last_mut() {
+                Some(group) if alternate => group.push(term),
+                _ => groups.push(vec![term]),
+            }
+            alternate = false;
+        }
+        Query { groups }
+    }
+
+    /// Returns true if the query has no terms.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// Splits `input` on whitespace, honouring `\ ` as an escaped space.
+///
+/// Returns every token with its byte offset within `input`.
+fn tokenize(input: &str) -> Vec<(usize, String)> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    let mut chars = input.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        if current.is_empty() {
+            start = index;
+        }
+        if c == '\\' && chars.peek().map(|(_, next)| *next) == Some(' ') {
+            current.push(' ');
+            chars.next();
+        } else if c.is_whitespace() {
+            if !current.is_empty() {
+                tokens.push((start, std::mem::take(&mut current)));
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push((start, current));
+    }
+    tokens
+}
+
+/// Parses a single token found at byte `offset` of the input, returns `None`
+/// if only operators are left.
+fn parse_term(token: &str, offset: usize) -> Option<QueryTerm> {
+    let (negated, rest) = match token.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let (kind, text) = if let Some(text) = rest.strip_prefix('\'') {
+        (TermKind::Exact, text)
+    } else {
+        match rest.strip_prefix('^') {
+            Some(text) => (TermKind::Prefix, text),
+            None => (TermKind::Default, rest),
+        }
+    };
+    // the operators in front of the text are single byte characters
+    let operators = token.len() - text.len();
+    let (kind, text) = match kind {
+        TermKind::Exact => (kind, text.to_string()),
+        // `\$` is a literal dollar sign, not an anchor
+        _ if text.ends_with("\\$") => (kind, format!("{}$", &text[..text.len() - 2])),
+        _ => {
+            match (kind, text.strip_suffix('$')) {
+                (TermKind::Prefix, Some(text)) => (TermKind::Equal, text.to_string()),
+                (_, Some(text)) => (TermKind::Suffix, text.to_string()),
+                (kind, None) => (kind, text.to_string()),
+            }
+        },
+    };
+    if text.is_empty() {
+        return None;
+    }
+    Some(QueryTerm {
+        text,
+        kind,
+        negated,
+        offset: offset + operators,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(text: &str, kind: TermKind, negated: bool) -> QueryTerm {
+        QueryTerm {
+            text: text.to_string(),
+            kind,
+            negated,
+            offset: 0,
+        }
+    }
+
+    /// Parses `input` ignoring the term offsets.
+    fn groups(input: &str) -> Vec<Vec<QueryTerm>> {
+        let mut query = Query::parse(input);
+        query.groups.iter_mut().flatten().for_each(|term| term.offset = 0);
+        query.groups
+    }
+
+    #[test]
+    fn test_empty_query() {
+        assert!(Query::parse("").is_empty());
+        assert!(Query::parse("   ").is_empty());
+        assert!(Query::parse("| ^ ! $").is_empty());
+    }
+
+    #[test]
+    fn test_and_terms() {
+        assert_eq!(
+            groups("docker  compose"),
+            vec![
+                vec![term("docker", TermKind::Default, false)],
+                vec![term("compose", TermKind::Default, false)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(
+            groups("docker !compose ^sudo .rs$ ^ls$ 'exact !'neg"),
+            vec![
+                vec![term("docker", TermKind::Default, false)],
+                vec![term("compose", TermKind::Default, true)],
+                vec![term("sudo", TermKind::Prefix, false)],
+                vec![term(".rs", TermKind::Suffix, false)],
+                vec![term("ls", TermKind::Equal, false)],
+                vec![term("exact", TermKind::Exact, false)],
+                vec![term("neg", TermKind::Exact, true)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_alternation() {
+        assert_eq!(
+            groups("^git | ^cargo build"),
+            vec![
+                vec![
+                    term("git", TermKind::Prefix, false),
+                    term("cargo", TermKind::Prefix, false)
+                ],
+                vec![term("build", TermKind::Default, false)],
+            ]
+        );
+        // a leading bar has nothing to alternate with
+        assert_eq!(groups("| ls"), vec![vec![term("ls", TermKind::Default, false)]]);
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(
+            groups(r"git\ push cost\$"),
+            vec![
+                vec![term("git push", TermKind::Default, false)],
+                vec![term("cost$", TermKind::Default, false)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_offsets() {
+        let query = Query::parse("  docker !^compose | 'up");
+        let offsets: Vec<usize> = query.groups.iter().flatten().map(|term| term.offset).collect();
+        assert_eq!(offsets, [2, 11, 22]);
+
+        // the offset skips the operators even when the text is unescaped
+        let query = Query::parse("echo !^cost\\$");
+        assert_eq!(query.groups[1][0].text, "cost$");
+        assert_eq!(query.groups[1][0].offset, 7);
+    }
+}
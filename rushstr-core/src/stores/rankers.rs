@@ -0,0 +1,276 @@
+use std::cmp::Ordering;
+
+use crate::{HItem, RankingType, SearchOptions, now_secs};
+
+/// A history item that matched the current query, ready to be ranked.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub item: HItem,
+    /// How well the item matched the query, higher is better.
+    pub score: i64,
+    /// Position of the item in the history, 0 being the most recent.
+    pub position: usize,
+    /// How many times the item was selected for a similar query.
+    pub learned: u32,
+}
+
+impl Candidate {
+    pub fn new(item: HItem, score: i64, position: usize) -> Self {
+        Self {
+            item,
+            score,
+            position,
+            learned: 0,
+        }
+    }
+}
+
+/// Defines the order in which matching items are shown.
+///
+/// Candidates are sorted with a stable sort, so items comparing as equal
+/// keep their history order.
+pub trait Ranker {
+    /// Short name shown in the UI.
+    fn name(&self) -> &'static str;
+
+    /// Compares two candidates, `Ordering::Less` puts `a` before `b`.
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering;
+}
+
+/// Most recent history entries first.
+pub struct RecencyRanker;
+
+impl Ranker for RecencyRanker {
+    fn name(&self) -> &'static str {
+        "recency"
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        a.position.cmp(&b.position)
+    }
+}
+
+/// Most selected items first, then best match.
+pub struct HitsRanker;
+
+impl Ranker for HitsRanker {
+    fn name(&self) -> &'static str {
+        "hits"
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        b.item.hits().cmp(&a.item.hits()).then(b.score.cmp(&a.score))
+    }
+}
+
+/// Frecency below which a command counts as never selected.
+const STALE_WEIGHT: f64 = 1e-9;
+
+/// Items selected often and recently first, then best match.
+pub struct FrecencyRanker {
+    /// current Unix time in seconds
+    now: u64,
+    /// seconds after which the weight of a hit is halved
+    half_life: u64,
+}
+
+impl FrecencyRanker {
+    pub fn new(now: u64, half_life: u64) -> Self {
+        Self { now, half_life }
+    }
+
+    /// Returns the frecency used for ordering.
+    ///
+    /// A weight left after some thirty half-lives is treated as zero, so
+    /// long forgotten commands fall back to the match score.
+    fn key(&self, item: &HItem) -> f64 {
+        let weight = item.frecency(self.now, self.half_life);
+        if weight < STALE_WEIGHT { 0.0 } else { weight }
+    }
+}
+
+impl Ranker for FrecencyRanker {
+    fn name(&self) -> &'static str {
+        "frecency"
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        self.key(&b.item)
+            .total_cmp(&self.key(&a.item))
+            .then(b.score.cmp(&a.score))
+    }
+}
+
+/// Best match first.
+pub struct ScoreRanker;
+
+impl Ranker for ScoreRanker {
+    fn name(&self) -> &'static str {
+        "score"
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        b.score.cmp(&a.score)
+    }
+}
+
+/// Commands in alphabetical order.
+pub struct AlphabeticalRanker;
+
+impl Ranker for AlphabeticalRanker {
+    fn name(&self) -> &'static str {
+        "alphabetical"
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        a.item.command().cmp(&b.item.command())
+    }
+}
+
+/// Shortest commands first.
+pub struct LengthRanker;
+
+impl Ranker for LengthRanker {
+    fn name(&self) -> &'static str {
+        "length"
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        a.item.command().chars().count().cmp(&b.item.command().chars().count())
+    }
+}
+
+/// Items selected before for a similar query first, then the order of
+/// another ranker.
+pub struct LearnedRanker(pub Box<dyn Ranker>);
+
+impl Ranker for LearnedRanker {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn compare(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        b.learned.cmp(&a.learned).then_with(|| self.0.compare(a, b))
+    }
+}
+
+/// Returns the built-in ranker selected in `options`.
+///
+/// The rankers ordering by relevance put first the items selected before
+/// for a similar query, the others keep their strict order.
+pub fn ranker_for(options: &SearchOptions) -> Box<dyn Ranker> {
+    match options.ranking {
+        RankingType::Frecency => {
+            Box::new(LearnedRanker(Box::new(FrecencyRanker::new(
+                now_secs(),
+                options.frecency_half_life,
+            ))))
+        },
+        RankingType::Recency => Box::new(RecencyRanker),
+        RankingType::Hits => Box::new(LearnedRanker(Box::new(HitsRanker))),
+        RankingType::Score => Box::new(LearnedRanker(Box::new(ScoreRanker))),
+        RankingType::Alphabetical => Box::new(AlphabeticalRanker),
+        RankingType::Length => Box::new(LengthRanker),
+    }
+}
+
+/// Sorts `candidates` with `ranker`, keeping the history order for ties.
+pub fn rank(candidates: &mut [Candidate], ranker: &dyn Ranker) {
+    candidates.sort_by(|a, b| ranker.compare(a, b));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(command: &str, score: i64, position: usize, hits: u64) -> Candidate {
+        let mut item = HItem::new(vec![command.to_string()]).unwrap();
+        for _ in 0..hits {
+            item.inc_hits();
+        }
+        Candidate::new(item, score, position)
+    }
+
+    fn ranked(ranker: &dyn Ranker) -> Vec<String> {
+        let mut candidates = vec![
+            candidate("git status", 10, 0, 0),
+            candidate("cargo build --release", 30, 1, 1),
+            candidate("ls", 20, 2, 3),
+            candidate("docker ps", 20, 3, 0),
+        ];
+        rank(&mut candidates, ranker);
+        candidates.into_iter().map(|c| c.item.command()).collect()
+    }
+
+    #[test]
+    fn test_recency() {
+        assert_eq!(
+            ranked(&RecencyRanker),
+            ["git status", "cargo build --release", "ls", "docker ps"]
+        );
+    }
+
+    #[test]
+    fn test_hits() {
+        assert_eq!(ranked(&HitsRanker), ["ls", "cargo build --release", "docker ps", "git status"]);
+    }
+
+    #[test]
+    fn test_frecency() {
+        const DAY: u64 = 24 * 60 * 60;
+        let now = 1_000 * DAY;
+        let ranker = FrecencyRanker::new(now, 14 * DAY);
+        let used = |command: &str, hits: u64, last_hit: u64| {
+            Candidate::new(HItem::new(vec![command.to_string()]).unwrap().with_hits(hits, last_hit), 0, 0)
+        };
+        let mut candidates = vec![
+            used("git status", 0, 0),
+            used("make deploy", 50, now - 730 * DAY),
+            used("ls", 1, now - 300),
+            used("cargo test", 3, now - DAY),
+        ];
+        rank(&mut candidates, &ranker);
+        let commands: Vec<String> = candidates.into_iter().map(|c| c.item.command()).collect();
+        // a single hit five minutes ago beats 50 hits two years ago, which
+        // decayed to nothing and keep their history order
+        assert_eq!(commands, ["cargo test", "ls", "git status", "make deploy"]);
+
+        // after many half-lives the hits no longer count
+        let ranker = FrecencyRanker::new(now_secs() + 100 * DAY, DAY);
+        assert_eq!(ranked(&ranker), ["cargo build --release", "ls", "docker ps", "git status"]);
+    }
+
+    #[test]
+    fn test_score() {
+        assert_eq!(ranked(&ScoreRanker), ["cargo build --release", "ls", "docker ps", "git status"]);
+    }
+
+    #[test]
+    fn test_alphabetical() {
+        assert_eq!(
+            ranked(&AlphabeticalRanker),
+            ["cargo build --release", "docker ps", "git status", "ls"]
+        );
+    }
+
+    #[test]
+    fn test_learned_first() {
+        let mut candidates = vec![
+            candidate("git status", 10, 0, 0),
+            candidate("cargo build --release", 30, 1, 0),
+            candidate("ls", 20, 2, 0),
+        ];
+        candidates[2].learned = 1;
+        rank(&mut candidates, &LearnedRanker(Box::new(ScoreRanker)));
+        let commands: Vec<String> = candidates.into_iter().map(|c| c.item.command()).collect();
+        assert_eq!(commands, ["ls", "cargo build --release", "git status"]);
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(
+            ranked(&LengthRanker),
+            ["ls", "docker ps", "git status", "cargo build --release"]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{HItem, HLines, Key, SearchOptions};
+use crate::{HItem, HLines, Key, MatcherRegistry, QueryError, RecentQuery, SearchOptions, SessionState};
 
 /// Defines the interface for a searchable and interactive item store.
 ///
@@ -10,14 +10,18 @@ pub trait StoreTrait {
     ///
     /// # Arguments
     ///
-    /// * `options` - A `SearchOptions` instance specifying filters like input
-    ///   text, search type (e.g., fuzzy, regex), and favorites-only mode.
+    /// * `options` - A `SearchOptions` instance specifying the input text,
+    ///   search type (e.g., fuzzy, regex) and filters such as favorites-only.
     ///
     /// # Returns
     ///
-    /// A list of `HItem`s matching the given options, or an error if the
-    /// operation fails.
-    fn items(&self, options: &SearchOptions) -> anyhow::Result<Vec<HItem>>;
+    /// A list of `HItem`s matching the given options, or a `QueryError` if
+    /// the search input cannot be run (e.g. an invalid regular expression).
+    fn items(&self, options: &SearchOptions) -> Result<Vec<HItem>, QueryError>;
+
+    /// Returns the matchers used by `items`, one for every search type the
+    /// store supports.
+    fn matchers(&self) -> &MatcherRegistry;
 
     /// Returns the total number of stored items.
     ///
@@ -42,6 +46,32 @@ pub trait StoreTrait {
     /// * `id` - A unique identifier corresponding to an `HItem`.
     fn mark_favorite(&self, id: &Key);
 
+    /// Makes the item with the given ID a favorite, or not.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique identifier corresponding to an `HItem`.
+    /// * `favorite` - Whether the item is a favorite.
+    fn set_favorite(&self, id: &Key, favorite: bool);
+
+    /// Tags the item with the given ID, unless it already has the tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique identifier corresponding to an `HItem`.
+    /// * `tag` - The tag, matched by the `tag:` filter.
+    fn add_tag(&self, id: &Key, tag: &str);
+
+    /// Deletes the item with the given ID along with its usage stats.
+    ///
+    /// The shell history is left untouched: the item is not loaded from it
+    /// again unless it is run after being deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique identifier corresponding to an `HItem`.
+    fn delete(&self, id: &Key) -> anyhow::Result<()>;
+
     /// Increments the "hit" count for the item with the given ID.
     ///
     /// Typically used to track item usage frequency for ranking or analytics.
@@ -50,4 +80,29 @@ pub trait StoreTrait {
     ///
     /// * `id` - A unique identifier corresponding to an `HItem`.
     fn mark_hit(&self, id: &Key);
+
+    /// Persists the search settings of the current session so the next one
+    /// can start from them.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - The search settings in use when rushstr exits.
+    fn save_session(&self, session: &SessionState) -> anyhow::Result<()>;
+
+    /// Returns the search settings saved by the previous session, if any.
+    fn load_session(&self) -> anyhow::Result<Option<SessionState>>;
+
+    /// Remembers a search, replacing an older search with the same input.
+    ///
+    /// Only the most recent searches are kept. The item selected is learned
+    /// for the prefixes of the input, and ranks higher next time a similar
+    /// query is typed.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The search and the item selected among its results.
+    fn save_query(&self, query: &RecentQuery) -> anyhow::Result<()>;
+
+    /// Returns the remembered searches, most recent first.
+    fn recent_queries(&self) -> anyhow::Result<Vec<RecentQuery>>;
 }
@@ -0,0 +1,223 @@
+use std::ops::Range;
+
+/// Where a term was found, allowing for typos.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypoMatch {
+    /// Number of edits between the term and the token.
+    pub distance: usize,
+    /// Characters of the token that are not in the term.
+    pub extra: usize,
+    /// Character range of the matched token within the haystack.
+    pub range: Range<usize>,
+}
+
+/// Returns the number of typos allowed in a term of `length` characters.
+///
+/// Short terms must match exactly, otherwise nearly every token would
+/// match them.
+pub fn max_edits(length: usize) -> usize {
+    match length {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    }
+}
+
+/// Finds the token of `haystack` closest to `term`, or `None` if no token is
+/// within `max_edits` of it.
+///
+/// Tokens are runs of alphanumeric characters and underscores. A token also
+/// matches when it starts with the term, so a term can be typed partially.
+/// Among tokens at the same distance, the one with the fewest extra
+/// characters, then the first one, wins.
+pub fn find_token(term: &[char], haystack: &str) -> Option<TypoMatch> {
+    if term.is_empty() {
+        return None;
+    }
+    let max = max_edits(term.len());
+    let term_mask = char_mask(term);
+    let mut best: Option<TypoMatch> = None;
+    let mut token = Vec::new();
+    let mut rows = Rows::default();
+    let mut start = 0;
+    for (index, c) in haystack.chars().chain(std::iter::once(' ')).enumerate() {
+        if c.is_alphanumeric() || c == '_' {
+            if token.is_empty() {
+                start = index;
+            }
+            token.push(c);
+            continue;
+        }
+        if token.is_empty() {
+            continue;
+        }
+        // every character of the term missing from the token costs an edit
+        let width = token.len().min(term.len() + max);
+        let missing = (term_mask & !char_mask(&token[..width])).count_ones() as usize;
+        if missing > max {
+            token.clear();
+            continue;
+        }
+        if let Some(found) = match_token(term, &token, max, start, &mut rows) {
+            let better = best
+                .as_ref()
+                .is_none_or(|best| (found.distance, found.extra) < (best.distance, best.extra));
+            if better {
+                best = Some(found);
+            }
+        }
+        token.clear();
+    }
+    best
+}
+
+/// Matches `term` against a single token starting at character `start`.
+fn match_token(term: &[char], token: &[char], max: usize, start: usize, rows: &mut Rows) -> Option<TypoMatch> {
+    // a longer token is too far from the term, unless the term is its prefix
+    let width = token.len().min(term.len() + max);
+    let last = rows.last_row(term, &token[..width], max)?;
+    let found = |length: usize| {
+        let distance = last[length];
+        (distance <= max).then(|| {
+            TypoMatch {
+                distance,
+                extra: token.len().saturating_sub(term.len()),
+                range: start..start + length,
+            }
+        })
+    };
+    let whole = (width == token.len()).then(|| found(token.len())).flatten();
+    // the term may be the beginning of a longer token
+    let prefix = (token.len() > term.len()).then(|| found(term.len())).flatten();
+    match (whole, prefix) {
+        (Some(whole), Some(prefix)) if prefix.distance < whole.distance => Some(prefix),
+        (None, prefix) => prefix,
+        (whole, _) => whole,
+    }
+}
+
+/// Returns a set of the characters of `text`, characters sharing a bit only
+/// make the set larger.
+fn char_mask(text: &[char]) -> u64 {
+    text.iter().fold(0, |mask, c| mask | 1 << (*c as u32 % 64))
+}
+
+/// The rows of the distance table, kept between tokens to avoid allocating
+/// for every one of them.
+#[derive(Default)]
+struct Rows {
+    before: Vec<usize>,
+    previous: Vec<usize>,
+    current: Vec<usize>,
+}
+
+impl Rows {
+    /// Fills the table of `a` against `b` and returns its last row, the
+    /// distances of `a` to every prefix of `b`.
+    ///
+    /// The distance is the optimal string alignment distance: insertions,
+    /// deletions, substitutions and transpositions of adjacent characters
+    /// count as one edit each.
+    ///
+    /// Returns `None` as soon as a whole row is further than `max`.
+    fn last_row(&mut self, a: &[char], b: &[char], max: usize) -> Option<&[usize]> {
+        let Rows {
+            before,
+            previous,
+            current,
+        } = self;
+        before.clear();
+        before.resize(b.len() + 1, 0);
+        previous.clear();
+        previous.extend(0..=b.len());
+        current.clear();
+        current.resize(b.len() + 1, 0);
+        for i in 1..=a.len() {
+            current[0] = i;
+            let mut row_min = i;
+            for j in 1..=b.len() {
+                let cost = usize::from(a[i - 1] != b[j - 1]);
+                let mut distance = (previous[j] + 1).min(current[j - 1] + 1).min(previous[j - 1] + cost);
+                if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                    distance = distance.min(before[j - 2] + 1);
+                }
+                current[j] = distance;
+                row_min = row_min.min(distance);
+            }
+            // every later row is at least as far
+            if row_min > max {
+                return None;
+            }
+            std::mem::swap(before, previous);
+            std::mem::swap(previous, current);
+        }
+        Some(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<char> {
+        text.chars().collect()
+    }
+
+    fn distance(a: &str, b: &str) -> Option<usize> {
+        Rows::default()
+            .last_row(&chars(a), &chars(b), 2)
+            .map(|row| row[b.chars().count()])
+            .filter(|distance| *distance <= 2)
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("kubectl", "kubectl"), Some(0));
+        assert_eq!(distance("kubeclt", "kubectl"), Some(1));
+        assert_eq!(distance("gti", "git"), Some(1));
+        assert_eq!(distance("dokcer", "docker"), Some(1));
+        assert_eq!(distance("pussh", "push"), Some(1));
+        assert_eq!(distance("psh", "push"), Some(1));
+        assert_eq!(distance("cargo", "crgp"), Some(2));
+        assert_eq!(distance("cargo", "docker"), None);
+        assert_eq!(distance("ls", "kubectl"), None);
+    }
+
+    #[test]
+    fn test_max_edits() {
+        assert_eq!(max_edits(2), 0);
+        assert_eq!(max_edits(3), 1);
+        assert_eq!(max_edits(7), 2);
+    }
+
+    #[test]
+    fn test_find_token() {
+        let found = find_token(&chars("kubeclt"), "sudo kubectl get pods").unwrap();
+        assert_eq!(found.distance, 1);
+        assert_eq!(found.range, 5..12);
+
+        // a partially typed term matches the beginning of a token
+        let found = find_token(&chars("relea"), "cargo build --release").unwrap();
+        assert_eq!(found.distance, 0);
+        assert_eq!(found.range, 14..19);
+        let found = find_token(&chars("relase"), "cargo build --release").unwrap();
+        assert_eq!(found.distance, 1);
+        assert_eq!(found.range, 14..21);
+
+        // the closest token wins over the first one
+        let found = find_token(&chars("push"), "git pish && git push").unwrap();
+        assert_eq!(found.distance, 0);
+        assert_eq!(found.range, 16..20);
+
+        assert_eq!(find_token(&chars("gti"), "ls -la"), None);
+        assert_eq!(
+            find_token(&chars("ls"), "lsof"),
+            Some(TypoMatch {
+                distance: 0,
+                extra: 2,
+                range: 0..2
+            })
+        );
+        assert_eq!(find_token(&chars("sl"), "ls"), None);
+    }
+}
@@ -1,27 +1,58 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::path::Path;
 use std::rc::Rc;
 use std::sync::Mutex;
 
 use bincode::config;
 use bincode::config::Configuration;
-use fuzzy_matcher::skim::SkimMatcherV2;
 use sled::Db;
 
-use crate::stores::matchers::{filter_items_exact, filter_items_monkey, filter_items_regex};
-use crate::{HItem, HLines, Key, MHItem, Scanner, SearchOptions, SearchType, StoreTrait, create_db};
+use crate::stores::learning::{LEARNING_TREE, MAX_LEARNED_PREFIXES};
+use crate::stores::matchers::filter_items;
+use crate::{
+    Candidate, HItem, HLines, Key, LearnedChoices, MHItem, MatcherRegistry, QueryError, RecentQuery, Scanner,
+    SearchOptions, SessionState, StoreTrait, create_db, now_secs, query_prefixes, rank, ranker_for,
+};
+
+/// Name of the database tree holding rushstr settings.
+const SETTINGS_TREE: &str = "settings";
+/// Key of the last session within the settings tree.
+const SESSION_KEY: &str = "session";
+/// Name of the database tree holding the recent searches, keyed by a
+/// growing sequence number.
+const QUERIES_TREE: &str = "queries";
+/// Number of recent searches kept.
+const MAX_RECENT_QUERIES: usize = 100;
+/// Name of the database tree holding the time deleted items were deleted,
+/// so they are not loaded again from the shell history.
+const DELETED_TREE: &str = "deleted";
 
 pub struct VectorStore {
     database: Db,
-    items: Vec<MHItem>,
-    items_index: HashMap<Key, MHItem>,
+    items: RefCell<Vec<MHItem>>,
+    items_index: RefCell<HashMap<Key, MHItem>>,
     config: Configuration,
-    matcher: SkimMatcherV2,
+    matchers: MatcherRegistry,
 }
 
 impl VectorStore {
-    pub fn new(scanner: Scanner) -> anyhow::Result<VectorStore> {
+    /// Creates a store from the items returned by `scanner`, keeping the
+    /// database in `data_dir` or in the default data directory when `None`.
+    pub fn new(scanner: Scanner, data_dir: Option<&Path>) -> anyhow::Result<VectorStore> {
+        VectorStore::with_matchers(scanner, data_dir, MatcherRegistry::default())
+    }
+
+    /// Creates a store like `new`, searching with the given `matchers`
+    /// instead of the built-in ones.
+    pub fn with_matchers(
+        scanner: Scanner,
+        data_dir: Option<&Path>,
+        matchers: MatcherRegistry,
+    ) -> anyhow::Result<VectorStore> {
         let config = config::standard();
-        let database = create_db()?;
+        let database = create_db(data_dir)?;
+        let deleted = database.open_tree(DELETED_TREE)?;
         let elements_in_history = scanner.load()?;
 
         let mut items = Vec::with_capacity(elements_in_history.len());
@@ -34,10 +65,19 @@ impl VectorStore {
             if items_index.contains_key(&key) {
                 continue;
             }
+            // a deleted item only comes back when it is run again
+            if let Some(bytes) = deleted.get(key)? {
+                let deleted_at = bytes.as_ref().try_into().map_or(u64::MAX, u64::from_be_bytes);
+                if item.last_run() <= deleted_at {
+                    continue;
+                }
+                deleted.remove(key)?;
+            }
 
             let final_item = if let Ok(Some(i_vec)) = database.get(key) {
-                let (hitem, _): (HItem, usize) = bincode::decode_from_slice(&i_vec, config)?;
-                hitem
+                let mut stored = HItem::decode(&i_vec, config)?;
+                stored.merge_scanned(&item);
+                stored
             } else {
                 let bytes = bincode::encode_to_vec(&item, config)?;
                 database.insert(key, bytes)?;
@@ -49,61 +89,111 @@ impl VectorStore {
             items.push(rc_item);
         }
 
-        // Sort items by number of hits (descending)
-        items.sort_by_key(|hitem| {
-            let lock = hitem.lock().unwrap();
-            std::cmp::Reverse(lock.hits())
-        });
-
-        let matcher = SkimMatcherV2::default();
         Ok(VectorStore {
             database,
-            items,
-            items_index,
+            items: RefCell::new(items),
+            items_index: RefCell::new(items_index),
             config,
-            matcher,
+            matchers,
         })
     }
+
+    /// Applies `update` to the item with the given ID and saves it.
+    fn update(&self, id: &Key, update: impl FnOnce(&mut HItem)) {
+        let items_index = self.items_index.borrow();
+        if let Some(mut h_item) = items_index.get(id).and_then(|item| item.lock().ok()) {
+            update(&mut h_item);
+            if let Ok(bytes) = bincode::encode_to_vec(&*h_item, self.config) {
+                let _ = self.database.insert(h_item.id(), bytes);
+            }
+        }
+    }
+
+    /// Returns what was learned for the longest known prefix of `input`.
+    fn learned_choices(&self, input: &str) -> Option<LearnedChoices> {
+        let tree = self.database.open_tree(LEARNING_TREE).ok()?;
+        query_prefixes(input).iter().rev().find_map(|prefix| {
+            let bytes = tree.get(prefix).ok()??;
+            bincode::decode_from_slice(&bytes, self.config)
+                .ok()
+                .map(|(learned, _)| learned)
+        })
+    }
+
+    /// Learns that `key` was selected for `input`.
+    fn learn(&self, input: &str, key: Key) -> anyhow::Result<()> {
+        let tree = self.database.open_tree(LEARNING_TREE)?;
+        let now = now_secs();
+        for prefix in query_prefixes(input) {
+            let mut learned: LearnedChoices = match tree.get(&prefix)? {
+                Some(bytes) => {
+                    bincode::decode_from_slice(&bytes, self.config)
+                        .map(|(learned, _)| learned)
+                        .unwrap_or_default()
+                },
+                None => LearnedChoices::default(),
+            };
+            learned.record(key, now);
+            tree.insert(prefix, bincode::encode_to_vec(&learned, self.config)?)?;
+        }
+
+        if tree.len() > MAX_LEARNED_PREFIXES {
+            let mut prefixes = Vec::with_capacity(tree.len());
+            for entry in tree.iter() {
+                let (prefix, bytes) = entry?;
+                let last_used = bincode::decode_from_slice::<LearnedChoices, _>(&bytes, self.config)
+                    .map_or(0, |(learned, _)| learned.last_used);
+                prefixes.push((last_used, prefix));
+            }
+            prefixes.sort();
+            for (_, prefix) in prefixes.iter().take(prefixes.len() - MAX_LEARNED_PREFIXES) {
+                tree.remove(prefix)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets how many times every candidate was selected for the query.
+fn apply_learned(candidates: &mut [Candidate], learned: &LearnedChoices) {
+    for candidate in candidates {
+        candidate.learned = learned.count(&candidate.item.id());
+    }
 }
 
 impl StoreTrait for VectorStore {
-    fn items(&self, options: &SearchOptions) -> anyhow::Result<Vec<HItem>> {
+    fn items(&self, options: &SearchOptions) -> Result<Vec<HItem>, QueryError> {
         let mut all_data = Vec::new();
-        for item in self.items.iter() {
+        for item in self.items.borrow().iter() {
             if let Ok(g_item) = item.lock() {
                 all_data.push(g_item.clone());
             }
         }
 
-        if options.input.is_empty() {
-            let mut f_all_data = Vec::new();
-            for item in all_data.into_iter() {
-                if options.favorites {
-                    if item.is_fav() {
-                        f_all_data.push(item);
-                    }
-                } else {
-                    f_all_data.push(item);
-                }
-            }
-            return Ok(f_all_data);
+        // the filters run first, then the matcher on the remaining text
+        let mut candidates = filter_items(&all_data, options, &self.matchers)?;
+        if let Some(learned) = self.learned_choices(&options.input) {
+            apply_learned(&mut candidates, &learned);
+        }
+        rank(&mut candidates, ranker_for(options).as_ref());
+        if let Some(max_results) = options.max_results {
+            candidates.truncate(max_results);
         }
+        Ok(candidates.into_iter().map(|candidate| candidate.item).collect())
+    }
 
-        let filtered_results = match options.search_type {
-            SearchType::MonkeyTyping => filter_items_monkey(&all_data, options, &self.matcher),
-            SearchType::Exact => filter_items_exact(&all_data, options),
-            SearchType::Regex => filter_items_regex(&all_data, options),
-        };
-        Ok(filtered_results)
+    fn matchers(&self) -> &MatcherRegistry {
+        &self.matchers
     }
 
     fn total(&self) -> anyhow::Result<HLines> {
-        Ok(self.items.len())
+        Ok(self.items.borrow().len())
     }
 
     fn favorites(&self) -> anyhow::Result<usize> {
         let favorites = self
             .items
+            .borrow()
             .iter()
             .filter_map(|item| item.lock().ok())
             .filter(|item| item.is_fav())
@@ -112,20 +202,228 @@ impl StoreTrait for VectorStore {
     }
 
     fn mark_favorite(&self, id: &Key) {
-        if let Some(mut h_item) = self.items_index.get(id).and_then(|item| item.lock().ok()) {
-            h_item.flip_fav();
-            if let Ok(bytes) = bincode::encode_to_vec(&*h_item, self.config) {
-                let _ = self.database.insert(h_item.id(), bytes);
+        self.update(id, HItem::flip_fav);
+    }
+
+    fn set_favorite(&self, id: &Key, favorite: bool) {
+        self.update(id, |h_item| {
+            if h_item.is_fav() != favorite {
+                h_item.flip_fav();
             }
+        });
+    }
+
+    fn add_tag(&self, id: &Key, tag: &str) {
+        self.update(id, |h_item| h_item.add_tag(tag));
+    }
+
+    fn delete(&self, id: &Key) -> anyhow::Result<()> {
+        if self.items_index.borrow_mut().remove(id).is_none() {
+            return Ok(());
         }
+        self.items
+            .borrow_mut()
+            .retain(|item| item.lock().map_or(true, |h_item| h_item.id() != *id));
+        self.database.remove(id)?;
+        self.database
+            .open_tree(DELETED_TREE)?
+            .insert(id, &now_secs().to_be_bytes())?;
+        Ok(())
     }
 
     fn mark_hit(&self, id: &Key) {
-        if let Some(mut h_item) = self.items_index.get(id).and_then(|item| item.lock().ok()) {
-            h_item.inc_hits();
-            if let Ok(bytes) = bincode::encode_to_vec(&*h_item, self.config) {
-                let _ = self.database.insert(h_item.id(), bytes);
+        self.update(id, HItem::inc_hits);
+    }
+
+    fn save_session(&self, session: &SessionState) -> anyhow::Result<()> {
+        let bytes = bincode::encode_to_vec(session, self.config)?;
+        self.database.open_tree(SETTINGS_TREE)?.insert(SESSION_KEY, bytes)?;
+        Ok(())
+    }
+
+    fn load_session(&self) -> anyhow::Result<Option<SessionState>> {
+        let Some(bytes) = self.database.open_tree(SETTINGS_TREE)?.get(SESSION_KEY)? else {
+            return Ok(None);
+        };
+        // a session written by an incompatible release is simply ignored
+        Ok(bincode::decode_from_slice(&bytes, self.config)
+            .ok()
+            .map(|(session, _)| session))
+    }
+
+    fn save_query(&self, query: &RecentQuery) -> anyhow::Result<()> {
+        let tree = self.database.open_tree(QUERIES_TREE)?;
+        for entry in tree.iter() {
+            let (key, bytes) = entry?;
+            let same_input = bincode::decode_from_slice::<RecentQuery, _>(&bytes, self.config)
+                .is_ok_and(|(recent, _)| recent.input == query.input);
+            if same_input {
+                tree.remove(key)?;
             }
         }
+        let id = self.database.generate_id()?;
+        tree.insert(id.to_be_bytes(), bincode::encode_to_vec(query, self.config)?)?;
+        while tree.len() > MAX_RECENT_QUERIES {
+            tree.pop_min()?;
+        }
+        if let Some(key) = query.selected {
+            self.learn(&query.input, key)?;
+        }
+        Ok(())
+    }
+
+    fn recent_queries(&self) -> anyhow::Result<Vec<RecentQuery>> {
+        let tree = self.database.open_tree(QUERIES_TREE)?;
+        let mut queries = Vec::new();
+        for entry in tree.iter().rev() {
+            let (_, bytes) = entry?;
+            // searches written by an incompatible release are skipped
+            if let Ok((query, _)) = bincode::decode_from_slice(&bytes, self.config) {
+                queries.push(query);
+            }
+        }
+        Ok(queries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::{env, fs};
+
+    use super::*;
+    use crate::{HScanner, MemCrawler, RankingType, reset_learning};
+
+    /// Scans the given commands, all last run at the same time.
+    struct RunAt(u64, Vec<&'static str>);
+
+    impl HScanner for RunAt {
+        fn load(&self) -> anyhow::Result<Vec<HItem>> {
+            self.1
+                .iter()
+                .map(|command| Ok(HItem::new(vec![command.to_string()])?.with_last_run(self.0)))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_recent_queries() {
+        let dir = env::temp_dir().join(format!("rushstr-queries-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = VectorStore::new(Rc::new(MemCrawler::new().unwrap()), Some(&dir)).unwrap();
+        let query = |input: &str| {
+            let options = SearchOptions {
+                input: input.to_string(),
+                ..SearchOptions::default()
+            };
+            RecentQuery::new(&options, None)
+        };
+
+        store.save_query(&query("psql")).unwrap();
+        store.save_query(&query("deploy prod")).unwrap();
+        store.save_query(&query("psql")).unwrap();
+        let inputs: Vec<String> = store.recent_queries().unwrap().into_iter().map(|q| q.input).collect();
+        assert_eq!(inputs, ["psql", "deploy prod"]);
+
+        for index in 0..MAX_RECENT_QUERIES {
+            store.save_query(&query(&index.to_string())).unwrap();
+        }
+        let recent = store.recent_queries().unwrap();
+        assert_eq!(recent.len(), MAX_RECENT_QUERIES);
+        assert_eq!(recent[0].input, (MAX_RECENT_QUERIES - 1).to_string());
+        drop(store);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_selection_learning() {
+        let dir = env::temp_dir().join(format!("rushstr-learning-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = VectorStore::new(Rc::new(MemCrawler::new().unwrap()), Some(&dir)).unwrap();
+        let options = |input: &str| {
+            SearchOptions {
+                input: input.to_string(),
+                ranking: RankingType::Score,
+                ..SearchOptions::default()
+            }
+        };
+        let first = |input: &str| store.items(&options(input)).unwrap()[0].command();
+        let chosen = store
+            .items(&options("git"))
+            .unwrap()
+            .into_iter()
+            .find(|item| item.command() == "git log --oneline")
+            .unwrap();
+        assert_ne!(first("gi"), "git log --oneline");
+
+        store
+            .save_query(&RecentQuery::new(&options("git lo"), Some(chosen.id())))
+            .unwrap();
+        // the same prefix, a shorter one and a longer one
+        assert_eq!(first("git lo"), "git log --oneline");
+        assert_eq!(first("gi"), "git log --oneline");
+        assert_eq!(first("git lo one"), "git log --oneline");
+
+        drop(store);
+        reset_learning(Some(&dir)).unwrap();
+        let store = VectorStore::new(Rc::new(MemCrawler::new().unwrap()), Some(&dir)).unwrap();
+        assert_ne!(store.items(&options("gi")).unwrap()[0].command(), "git log --oneline");
+        drop(store);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_batch_updates_and_delete() {
+        let dir = env::temp_dir().join(format!("rushstr-delete-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let commands = vec!["make", "make test", "rm -rf /tmp/x"];
+        // sled releases the lock of a dropped database from a background thread
+        let open = |last_run: u64| {
+            let scanner: Scanner = Rc::new(RunAt(last_run, commands.clone()));
+            (0..50)
+                .find_map(|_| {
+                    VectorStore::new(scanner.clone(), Some(&dir))
+                        .inspect_err(|_| std::thread::sleep(std::time::Duration::from_millis(20)))
+                        .ok()
+                })
+                .unwrap()
+        };
+        let commands_of = |store: &VectorStore| {
+            let mut found: Vec<String> = store
+                .items(&SearchOptions::default())
+                .unwrap()
+                .iter()
+                .map(HItem::command)
+                .collect();
+            found.sort();
+            found
+        };
+
+        let store = open(now_secs() - 60);
+        let items = store.items(&SearchOptions::default()).unwrap();
+        let key = |command: &str| items.iter().find(|item| item.command() == command).unwrap().id();
+        store.set_favorite(&key("make"), true);
+        store.set_favorite(&key("make"), true);
+        store.add_tag(&key("make test"), "build");
+        store.add_tag(&key("make test"), "build");
+        store.delete(&key("rm -rf /tmp/x")).unwrap();
+        assert_eq!(store.total().unwrap(), 2);
+        assert_eq!(store.favorites().unwrap(), 1);
+        drop(store);
+
+        // deleted items stay deleted, the other changes are kept
+        let store = open(now_secs() - 60);
+        assert_eq!(commands_of(&store), ["make", "make test"]);
+        let tagged = store.items(&SearchOptions::default()).unwrap();
+        let tagged = tagged.iter().find(|item| item.command() == "make test").unwrap();
+        assert_eq!(tagged.tags(), ["build"]);
+        assert_eq!(store.favorites().unwrap(), 1);
+        drop(store);
+
+        // until they are run again
+        let store = open(now_secs() + 60);
+        assert_eq!(commands_of(&store), ["make", "make test", "rm -rf /tmp/x"]);
+        drop(store);
+        let _ = fs::remove_dir_all(&dir);
     }
 }
@@ -1,6 +1,7 @@
+use bincode::config::Configuration;
 use bincode::{Decode, Encode};
 
-use crate::{HLines, Key, hash_string};
+use crate::{HLines, Key, hash_string, now_secs};
 
 /// Represents a multi-line shell command entry.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
@@ -10,6 +11,29 @@ pub struct HItem {
     id: Key,
     favorite: bool,
     hits: u64,
+    /// Unix time (seconds) of the last hit, 0 if never hit.
+    last_hit: u64,
+    /// User defined labels.
+    tags: Vec<String>,
+    /// Directory the command last ran in, if the history records it.
+    dir: Option<String>,
+    /// Exit status of the last run, if the history records it.
+    exit: Option<i32>,
+    /// Unix time (seconds) of the last run according to the history, 0 if
+    /// unknown.
+    last_run: u64,
+    /// History file the command was read from, if it came from one.
+    source: Option<String>,
+}
+
+/// `HItem` layout written by earlier releases, which recorded only the
+/// favorite flag and the hits.
+#[derive(Decode)]
+struct HItemV1 {
+    command: Vec<String>,
+    id: Key,
+    favorite: bool,
+    hits: u64,
 }
 
 impl HItem {
@@ -30,9 +54,75 @@ impl HItem {
             id,
             favorite: false,
             hits: 0,
+            last_hit: 0,
+            tags: Vec::new(),
+            dir: None,
+            exit: None,
+            last_run: 0,
+            source: None,
         })
     }
 
+    /// Sets the directory the command last ran in.
+    pub fn with_dir(mut self, dir: impl Into<String>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the exit status of the last run.
+    pub fn with_exit(mut self, exit: i32) -> Self {
+        self.exit = Some(exit);
+        self
+    }
+
+    /// Sets the number of hits and the Unix time (seconds) of the last one.
+    pub fn with_hits(mut self, hits: u64, last_hit: u64) -> Self {
+        self.hits = hits;
+        self.last_hit = last_hit;
+        self
+    }
+
+    /// Sets the Unix time (seconds) of the last run.
+    pub fn with_last_run(mut self, last_run: u64) -> Self {
+        self.last_run = last_run;
+        self
+    }
+
+    /// Sets the history file the command was read from.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Updates the run metadata with the one found by a newer scan of the
+    /// history, keeping what the user set (favorite, hits, tags).
+    pub fn merge_scanned(&mut self, scanned: &HItem) {
+        self.last_run = self.last_run.max(scanned.last_run);
+        if scanned.source.is_some() {
+            self.source = scanned.source.clone();
+        }
+        if scanned.dir.is_some() {
+            self.dir = scanned.dir.clone();
+        }
+        if scanned.exit.is_some() {
+            self.exit = scanned.exit;
+        }
+    }
+
+    /// Decodes an item stored in the database, accepting the layout written
+    /// by earlier releases.
+    pub fn decode(bytes: &[u8], config: Configuration) -> anyhow::Result<HItem> {
+        if let Ok((item, _)) = bincode::decode_from_slice::<HItem, _>(bytes, config) {
+            return Ok(item);
+        }
+        let (v1, _): (HItemV1, usize) = bincode::decode_from_slice(bytes, config)?;
+        let mut item = HItem::new(v1.command)?;
+        item.id = v1.id;
+        item.favorite = v1.favorite;
+        item.hits = v1.hits;
+        Ok(item)
+    }
+
     /// Returns the number of lines in the command.
     ///
     /// # Returns
@@ -67,6 +157,54 @@ impl HItem {
         self.command.join("\n")
     }
 
+    /// Maps every character of `command()` back to the line and character
+    /// it comes from in `command_lines()`.
+    ///
+    /// A space collapsing a run of whitespace maps to the first character of
+    /// that run, the spaces joining two lines map to `None`.
+    ///
+    /// # Returns
+    ///
+    /// A vector with one entry per character of `command()`, holding the
+    /// line index and the character index within that line.
+    pub fn command_positions(&self) -> Vec<Option<(usize, usize)>> {
+        let mut positions = Vec::new();
+        for (line_index, line) in self.command.iter().enumerate() {
+            if line_index > 0 {
+                positions.push(None);
+            }
+            let mut seen_word = false;
+            let mut gap = None;
+            for (char_index, c) in line.chars().enumerate() {
+                if c.is_whitespace() {
+                    if seen_word && gap.is_none() {
+                        gap = Some(char_index);
+                    }
+                } else {
+                    if let Some(gap_index) = gap.take() {
+                        positions.push(Some((line_index, gap_index)));
+                    }
+                    positions.push(Some((line_index, char_index)));
+                    seen_word = true;
+                }
+            }
+        }
+        positions
+    }
+
+    /// Maps every character of `raw_text()` back to the line and character
+    /// it comes from in `command_lines()`, the newlines map to `None`.
+    pub fn raw_positions(&self) -> Vec<Option<(usize, usize)>> {
+        let mut positions = Vec::new();
+        for (line_index, line) in self.command.iter().enumerate() {
+            if line_index > 0 {
+                positions.push(None);
+            }
+            positions.extend((0..line.chars().count()).map(|char_index| Some((line_index, char_index))));
+        }
+        positions
+    }
+
     pub fn command_lines(&self) -> Vec<String> {
         self.command.clone()
     }
@@ -83,11 +221,177 @@ impl HItem {
         self.favorite = !self.favorite;
     }
 
+    /// Increments the hit count and records the current time as the last hit.
     pub fn inc_hits(&mut self) {
-        self.hits += 1
+        self.hits += 1;
+        self.last_hit = now_secs();
     }
 
     pub fn hits(&self) -> u64 {
         self.hits
     }
+
+    /// Returns the Unix time (seconds) of the last hit, 0 if never hit.
+    pub fn last_hit(&self) -> u64 {
+        self.last_hit
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Adds `tag` unless the item already has it.
+    pub fn add_tag(&mut self, tag: &str) {
+        if !self.tags.iter().any(|t| t == tag) {
+            self.tags.push(tag.to_string());
+        }
+    }
+
+    pub fn dir(&self) -> Option<&str> {
+        self.dir.as_deref()
+    }
+
+    pub fn exit(&self) -> Option<i32> {
+        self.exit
+    }
+
+    /// Returns the history file the command was read from, if known.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns the Unix time (seconds) of the last run recorded in the
+    /// history, 0 if unknown.
+    pub fn last_run(&self) -> u64 {
+        self.last_run
+    }
+
+    /// Returns the Unix time (seconds) the command was last used, run from
+    /// the shell or selected in rushstr, 0 if unknown.
+    pub fn last_used(&self) -> u64 {
+        self.last_run.max(self.last_hit)
+    }
+
+    /// Returns the hit count decayed by the age of the last hit.
+    ///
+    /// The weight of the hits halves every `half_life` seconds, so a command
+    /// used often long ago ends up below one used a few times recently.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The current Unix time in seconds.
+    /// * `half_life` - Seconds after which the hit weight is halved.
+    pub fn frecency(&self, now: u64, half_life: u64) -> f64 {
+        if self.hits == 0 {
+            return 0.0;
+        }
+        let age = now.saturating_sub(self.last_hit) as f64;
+        self.hits as f64 * 0.5_f64.powf(age / half_life.max(1) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::{Encode, config};
+
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn item(command: &str, hits: u64, last_hit: u64) -> HItem {
+        let mut item = HItem::new(vec![command.to_string()]).unwrap();
+        item.hits = hits;
+        item.last_hit = last_hit;
+        item
+    }
+
+    #[test]
+    fn test_frecency_decays_with_age() {
+        let now = 1000 * DAY;
+        let fresh = item("git status", 2, now);
+        let stale = item("git stash", 50, now - 730 * DAY);
+        assert_eq!(fresh.frecency(now, 14 * DAY), 2.0);
+        assert!(stale.frecency(now, 14 * DAY) < fresh.frecency(now, 14 * DAY));
+        assert_eq!(item("ls", 4, now - 14 * DAY).frecency(now, 14 * DAY), 2.0);
+        assert_eq!(item("ls", 0, now).frecency(now, 14 * DAY), 0.0);
+    }
+
+    #[test]
+    fn test_command_positions() {
+        let item = HItem::new(vec!["  git   commit".to_string(), "".to_string(), "-m x ".to_string()]).unwrap();
+        let positions = item.command_positions();
+        assert_eq!(positions.len(), item.command().chars().count());
+        assert_eq!(item.command(), "git commit  -m x");
+        assert_eq!(positions[0], Some((0, 2)));
+        assert_eq!(positions[3], Some((0, 5)));
+        assert_eq!(positions[4], Some((0, 8)));
+        assert_eq!(positions[10], None);
+        assert_eq!(positions[11], None);
+        assert_eq!(positions[12], Some((2, 0)));
+        assert_eq!(positions[15], Some((2, 3)));
+    }
+
+    #[test]
+    fn test_raw_positions() {
+        let item = HItem::new(vec!["a b".to_string(), "".to_string(), "  c".to_string()]).unwrap();
+        let positions = item.raw_positions();
+        assert_eq!(positions.len(), item.raw_text().chars().count());
+        assert_eq!(positions[2], Some((0, 2)));
+        assert_eq!(positions[3], None);
+        assert_eq!(positions[4], None);
+        assert_eq!(positions[7], Some((2, 2)));
+    }
+
+    #[test]
+    fn test_merge_scanned() {
+        let mut stored = item("make", 3, 10).with_last_run(100).with_exit(2);
+        stored.add_tag("build");
+        stored.merge_scanned(&item("make", 0, 0).with_last_run(50).with_dir("/src"));
+        assert_eq!(stored.last_run(), 100);
+        assert_eq!(stored.dir(), Some("/src"));
+        assert_eq!(stored.exit(), Some(2));
+        assert_eq!(stored.hits(), 3);
+        assert_eq!(stored.tags(), ["build"]);
+        assert_eq!(stored.last_used(), 100);
+    }
+
+    #[test]
+    fn test_inc_hits_records_time() {
+        let mut item = item("ls", 0, 0);
+        item.inc_hits();
+        assert_eq!(item.hits(), 1);
+        assert!(item.last_hit() > 0);
+    }
+
+    #[test]
+    fn test_decode_legacy_layout() {
+        #[derive(Encode)]
+        struct Legacy {
+            command: Vec<String>,
+            id: Key,
+            favorite: bool,
+            hits: u64,
+        }
+        let legacy = Legacy {
+            command: vec!["ls -la".to_string()],
+            id: [7; 32],
+            favorite: true,
+            hits: 3,
+        };
+        let bytes = bincode::encode_to_vec(&legacy, config::standard()).unwrap();
+        let decoded = HItem::decode(&bytes, config::standard()).unwrap();
+        assert_eq!(decoded.command(), "ls -la");
+        assert!(decoded.is_fav());
+        assert_eq!(decoded.hits(), 3);
+        assert_eq!(decoded.last_hit(), 0);
+
+        let mut current = item("ls", 1, 42)
+            .with_dir("/tmp")
+            .with_exit(1)
+            .with_last_run(7)
+            .with_source("~/.zsh_history");
+        current.add_tag("work");
+        let bytes = bincode::encode_to_vec(&current, config::standard()).unwrap();
+        assert_eq!(HItem::decode(&bytes, config::standard()).unwrap(), current);
+    }
 }
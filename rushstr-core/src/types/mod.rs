@@ -1,2 +1,4 @@
 pub mod common_types;
 pub mod history_item;
+pub mod recent_query;
+pub mod session_state;
@@ -0,0 +1,28 @@
+use bincode::{Decode, Encode};
+
+use crate::{Key, SearchOptions, SearchType, now_secs};
+
+/// A search typed in a previous session, recalled from the search box.
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct RecentQuery {
+    /// the query typed in the search box
+    pub input: String,
+    pub search_type: SearchType,
+    /// key of the item selected among the results, if any was
+    pub selected: Option<Key>,
+    /// Unix time in seconds of the search
+    pub time: u64,
+}
+
+impl RecentQuery {
+    /// Creates the query run with `options` now, `selected` being the item
+    /// picked among its results.
+    pub fn new(options: &SearchOptions, selected: Option<Key>) -> Self {
+        RecentQuery {
+            input: options.input.clone(),
+            search_type: options.search_type.clone(),
+            selected,
+            time: now_secs(),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use bincode::{Decode, Encode};
+
+use crate::{SearchOptions, SearchType};
+
+/// Search settings remembered from the previous rushstr session.
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct SessionState {
+    pub search_type: SearchType,
+    pub favorites: bool,
+    /// the last query typed in the search box
+    pub input: String,
+}
+
+impl From<&SearchOptions> for SessionState {
+    fn from(options: &SearchOptions) -> Self {
+        SessionState {
+            search_type: options.search_type.clone(),
+            favorites: options.favorites_only(),
+            input: options.input.clone(),
+        }
+    }
+}
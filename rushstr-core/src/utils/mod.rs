@@ -1 +1,2 @@
+pub mod shell_syntax;
 pub mod utilities;
@@ -0,0 +1,318 @@
+use std::ops::Range;
+
+/// The kind of a shell token worth colouring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// The word run as a command, or a reserved word such as `if` or `do`.
+    Command,
+    /// An option such as `-n` or `--force`.
+    Flag,
+    /// A single or double quoted string.
+    String,
+    /// A parameter expansion such as `$HOME`, or the name of an assignment.
+    Variable,
+    /// A control operator or a redirection: `|`, `&&`, `;`, `2>&1`, ...
+    Operator,
+    /// A comment, up to the end of the line.
+    Comment,
+}
+
+/// A token of a command line: its kind and its character range in the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    pub kind: SyntaxKind,
+    pub range: Range<usize>,
+}
+
+/// Reserved words, and the prefix commands after which a command is still
+/// expected.
+const RESERVED: [&str; 12] = [
+    "if", "then", "else", "elif", "do", "while", "until", "time", "!", "{", "exec", "sudo",
+];
+
+/// Tokenizes the `lines` of a command, returning the tokens of every line.
+///
+/// This is a lightweight approximation of the shell grammar, made for
+/// colouring: arguments other than flags, strings and variables produce no
+/// token. Quotes left open and lines ending with `\` carry over to the next
+/// line, otherwise every line starts a new command.
+pub fn tokenize_shell(lines: &[String]) -> Vec<Vec<SyntaxToken>> {
+    let mut tokenizer = Tokenizer::default();
+    lines.iter().map(|line| tokenizer.line(line)).collect()
+}
+
+#[derive(Default)]
+struct Tokenizer {
+    /// The quote of a string continuing from the previous line.
+    open_quote: Option<char>,
+    /// Whether the previous line ended with a `\` continuation.
+    continued: bool,
+    /// Whether the next word is a command.
+    expect_command: bool,
+}
+
+impl Tokenizer {
+    fn line(&mut self, line: &str) -> Vec<SyntaxToken> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut tokens = Vec::new();
+        let mut index = 0;
+        if !self.continued && self.open_quote.is_none() {
+            self.expect_command = true;
+        }
+        self.continued = false;
+
+        if let Some(quote) = self.open_quote.take() {
+            index = self.string_end(&chars, 0, quote);
+            push(&mut tokens, SyntaxKind::String, 0..index);
+        }
+
+        while index < chars.len() {
+            let c = chars[index];
+            let start = index;
+            if c.is_whitespace() {
+                index += 1;
+            } else if c == '#' {
+                index = chars.len();
+                push(&mut tokens, SyntaxKind::Comment, start..index);
+            } else if c == '\'' || c == '"' {
+                index = self.string_end(&chars, index + 1, c);
+                push(&mut tokens, SyntaxKind::String, start..index);
+                self.expect_command = false;
+            } else if c == '$' && chars.get(index + 1) == Some(&'(') {
+                index += 2;
+                push(&mut tokens, SyntaxKind::Operator, start..index);
+                self.expect_command = true;
+            } else if c == '$' {
+                index = variable_end(&chars, index);
+                push(&mut tokens, SyntaxKind::Variable, start..index);
+                self.expect_command = false;
+            } else if let Some(end) = operator_end(&chars, index) {
+                let operator: String = chars[start..end].iter().collect();
+                index = end;
+                push(&mut tokens, SyntaxKind::Operator, start..index);
+                // redirections are followed by a file name, not a command
+                if !operator.contains(['<', '>']) {
+                    self.expect_command = true;
+                }
+            } else if c == '\\' && index + 1 == chars.len() {
+                index += 1;
+                self.continued = true;
+            } else {
+                index = word_end(&chars, index);
+                self.word(&chars[start..index], start, &mut tokens);
+            }
+        }
+        tokens
+    }
+
+    /// Classifies the word `word` starting at character `start`.
+    fn word(&mut self, word: &[char], start: usize, tokens: &mut Vec<SyntaxToken>) {
+        let text: String = word.iter().collect();
+        let range = start..start + word.len();
+        if word.len() > 1 && word[0] == '-' {
+            push(tokens, SyntaxKind::Flag, range);
+        } else if self.expect_command {
+            match assignment_name(word) {
+                Some(name) => push(tokens, SyntaxKind::Variable, start..start + name),
+                None => {
+                    push(tokens, SyntaxKind::Command, range);
+                    self.expect_command = RESERVED.contains(&text.as_str());
+                },
+            }
+        }
+    }
+
+    /// Returns the index after the string closed by `quote` from `index`,
+    /// remembering the quote when the string goes on to the next line.
+    fn string_end(&mut self, chars: &[char], mut index: usize, quote: char) -> usize {
+        while index < chars.len() {
+            match chars[index] {
+                '\\' if quote == '"' => index += 2,
+                c if c == quote => return index + 1,
+                _ => index += 1,
+            }
+        }
+        self.open_quote = Some(quote);
+        chars.len()
+    }
+}
+
+fn push(tokens: &mut Vec<SyntaxToken>, kind: SyntaxKind, range: Range<usize>) {
+    if !range.is_empty() {
+        tokens.push(SyntaxToken { kind, range });
+    }
+}
+
+/// Returns the end of the control operator or redirection at `index`, if
+/// any. A file descriptor number before a redirection is part of it.
+fn operator_end(chars: &[char], index: usize) -> Option<usize> {
+    let mut end = index;
+    while end < chars.len() && chars[end].is_ascii_digit() {
+        end += 1;
+    }
+    let digits = end > index;
+    let rest: String = chars[end..chars.len().min(end + 3)].iter().collect();
+
+    const REDIRECTIONS: [&str; 8] = [">&", "<&", ">>", "<<", "&>", "<>", ">", "<"];
+    const CONTROLS: [&str; 10] = ["&&", "||", ";;", "|&", "|", "&", ";", "(", ")", "`"];
+    let operator = REDIRECTIONS
+        .iter()
+        .chain(if digits { &[][..] } else { &CONTROLS[..] })
+        .find(|operator| rest.starts_with(*operator))?;
+    end += operator.chars().count();
+    // `2>&1` and `>&-` name a descriptor
+    if operator.ends_with('&') {
+        while end < chars.len() && (chars[end].is_ascii_digit() || chars[end] == '-') {
+            end += 1;
+        }
+    }
+    Some(end)
+}
+
+/// Returns the end of the parameter expansion starting with the `$` at
+/// `index`.
+fn variable_end(chars: &[char], index: usize) -> usize {
+    let mut end = index + 1;
+    match chars.get(end) {
+        Some('{') => {
+            while end < chars.len() && chars[end] != '}' {
+                end += 1;
+            }
+            (end + 1).min(chars.len())
+        },
+        Some(c) if c.is_alphabetic() || *c == '_' => {
+            while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                end += 1;
+            }
+            end
+        },
+        Some(c) if c.is_ascii_digit() || "?#@*!$-".contains(*c) => end + 1,
+        _ => end,
+    }
+}
+
+/// Returns the end of the plain word starting at `index`: it stops before
+/// whitespace, quotes, expansions and operators, and keeps escaped
+/// characters.
+fn word_end(chars: &[char], index: usize) -> usize {
+    let mut end = index;
+    while end < chars.len() {
+        match chars[end] {
+            '\\' if end + 1 < chars.len() => end += 2,
+            '\\' if end > index => return end,
+            c if c.is_whitespace() || "'\"$|&;<>()`".contains(c) => return end,
+            _ => end += 1,
+        }
+    }
+    end.max(index + 1)
+}
+
+/// Returns the length of the name when `word` is an assignment such as
+/// `LANG=C`.
+fn assignment_name(word: &[char]) -> Option<usize> {
+    let name = word.iter().position(|c| *c == '=')?;
+    let valid =
+        name > 0 && !word[0].is_ascii_digit() && word[..name].iter().all(|c| c.is_ascii_alphanumeric() || *c == '_');
+    valid.then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the text and kind of every token of `lines`, line by line.
+    fn tokens(lines: &[&str]) -> Vec<Vec<(String, SyntaxKind)>> {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        tokenize_shell(&lines)
+            .into_iter()
+            .zip(&lines)
+            .map(|(tokens, line)| {
+                tokens
+                    .into_iter()
+                    .map(|token| {
+                        let text = line.chars().skip(token.range.start).take(token.range.len()).collect();
+                        (text, token.kind)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn token(text: &str, kind: SyntaxKind) -> (String, SyntaxKind) {
+        (text.to_string(), kind)
+    }
+
+    #[test]
+    fn test_pipeline() {
+        use SyntaxKind::*;
+        assert_eq!(
+            tokens(&["LANG=C grep -rn --color \"$PAT\" src | sort -u > out.txt 2>&1 # find it"]),
+            vec![vec![
+                token("LANG", Variable),
+                token("grep", Command),
+                token("-rn", Flag),
+                token("--color", Flag),
+                token("\"$PAT\"", String),
+                token("|", Operator),
+                token("sort", Command),
+                token("-u", Flag),
+                token(">", Operator),
+                token("2>&1", Operator),
+                token("# find it", Comment),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_words_and_expansions() {
+        use SyntaxKind::*;
+        assert_eq!(
+            tokens(&["cd ~/src && echo ${HOME}/x$1 'it''s' a#b; ls $(pwd)"]),
+            vec![vec![
+                token("cd", Command),
+                token("&&", Operator),
+                token("echo", Command),
+                token("${HOME}", Variable),
+                token("$1", Variable),
+                token("'it'", String),
+                token("'s'", String),
+                token(";", Operator),
+                token("ls", Command),
+                token("$(", Operator),
+                token("pwd", Command),
+                token(")", Operator),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_multi_line() {
+        use SyntaxKind::*;
+        assert_eq!(
+            tokens(&[
+                "for f in *.log; do",
+                "  gzip -9 $f",
+                "done",
+                "echo 'a",
+                "b' \\",
+                "  --verbose x",
+            ]),
+            vec![
+                vec![token("for", Command), token(";", Operator), token("do", Command)],
+                vec![token("gzip", Command), token("-9", Flag), token("$f", Variable)],
+                vec![token("done", Command)],
+                vec![token("echo", Command), token("'a", String)],
+                vec![token("b'", String)],
+                vec![token("--verbose", Flag)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranges_are_chars() {
+        let lines = vec!["echo café | wc -m".to_string()];
+        let tokens = tokenize_shell(&lines);
+        assert_eq!(tokens[0][1].range, 10..11);
+        assert_eq!(tokens[0][3].range, 15..17);
+    }
+}
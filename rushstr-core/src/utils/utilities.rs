@@ -1,13 +1,15 @@
 use std::fs::{OpenOptions, read_to_string};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{env, fs};
 
 use regex::Regex;
 use sha2::{Digest, Sha256};
 use sled::Db;
 
-use crate::{RushstrFiles, Shell};
+use crate::stores::learning::LEARNING_TREE;
+use crate::{RushstrFiles, Shell, UserConfig};
 
 /// Detects the current user's shell based on the `SHELL` environment variable.
 ///
@@ -40,8 +42,9 @@ pub fn detect_shell() -> Shell {
 
 /// Reads the shell history file based on the given `Shell` type.
 ///
-/// This function returns the history as a list of strings, one per line.
-/// If the history file cannot be found or read, an empty list is returned.
+/// This function returns the history as a list of entries, one per command.
+/// If the history file cannot be found or read, a placeholder entry is
+/// returned.
 ///
 /// # Arguments
 ///
@@ -49,32 +52,89 @@ pub fn detect_shell() -> Shell {
 ///
 /// # Returns
 ///
-/// A vector of strings representing each line in the history file.
+/// A vector of `HistoryEntry` representing each command in the history file.
 ///
 /// # Notes
 ///
 /// - For `Zsh`, it reads from `~/.zsh_history`
 /// - For `Bash`, it reads from `~/.bash_history`
 /// - For `Csh`, it reads from `~/.history` (common default)
-pub fn read_history(shell: Shell) -> Vec<String> {
-    let home = match env::var("HOME") {
-        Ok(path) => PathBuf::from(path),
-        Err(_) => return vec!["default1".to_string()],
+pub fn read_history(shell: Shell) -> Vec<HistoryEntry> {
+    let placeholder = |command: &str| {
+        vec![HistoryEntry {
+            command: command.to_string(),
+            timestamp: None,
+        }]
     };
-
-    let history_file = match shell {
-        Shell::Zsh => home.join(".zsh_history"),
-        Shell::Bash => home.join(".bash_history"),
-        Shell::Csh => home.join(".history"),
-        Shell::Unknown => return vec!["default2".to_string()],
+    if env::var("HOME").is_err() {
+        return placeholder("default1");
+    }
+    let Some(history_file) = shell_history_file(&shell) else {
+        return placeholder("default2");
     };
 
-    let contents = match fs::read(&history_file) {
-        Ok(data) => String::from_utf8_lossy(&data).into_owned(),
-        Err(_) => return vec!["failed to read history".to_string()],
-    };
+    match read_history_file(&history_file) {
+        Ok(commands) => commands,
+        Err(_) => placeholder("failed to read history"),
+    }
+}
+
+/// Returns the default history file of `shell`, `None` for an unknown shell
+/// or when the home directory is not set.
+pub fn shell_history_file(shell: &Shell) -> Option<PathBuf> {
+    let home = PathBuf::from(env::var("HOME").ok()?);
+    match shell {
+        Shell::Zsh => Some(home.join(".zsh_history")),
+        Shell::Bash => Some(home.join(".bash_history")),
+        Shell::Csh => Some(home.join(".history")),
+        Shell::Unknown => None,
+    }
+}
+
+/// Reads and parses a single history file.
+///
+/// Both plain (Bash, Csh) and Zsh extended history formats are understood.
+///
+/// # Arguments
+///
+/// * `history_file` - Path of the file to read, a leading `~/` is expanded to
+///   the home directory.
+///
+/// # Returns
+///
+/// The commands in the file, oldest first, or an error if the file cannot be
+/// read.
+pub fn read_history_file(history_file: &Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    let history_file = expand_tilde(history_file);
+    let data = fs::read(&history_file)?;
+    let contents = String::from_utf8_lossy(&data).into_owned();
+    Ok(parse_history(&contents))
+}
+
+/// Replaces a leading `~` in `path` with the home directory.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var("HOME")) {
+        (Ok(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// A command read from a history file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub command: String,
+    /// Unix time (seconds) the command ran, if the history records it.
+    pub timestamp: Option<u64>,
+}
+
+/// Splits the contents of a history file into commands.
+///
+/// Timestamps are taken from Zsh extended history entries and from the
+/// `#<time>` lines Bash writes when `HISTTIMEFORMAT` is set.
+fn parse_history(contents: &str) -> Vec<HistoryEntry> {
     let mut commands = Vec::new();
     let mut current_command = String::new();
+    let mut timestamp = None;
     let mut in_multiline = false;
 
     for line in contents.lines() {
@@ -87,14 +147,19 @@ pub fn read_history(shell: Shell) -> Vec<String> {
             continue;
         }
 
-        if line.starts_with(": ") {
+        if let Some(rest) = line.strip_prefix(": ") {
             // Zsh extended history entry
             if !current_command.is_empty() {
-                commands.push(unescape_zsh(&current_command));
+                commands.push(HistoryEntry {
+                    command: unescape_zsh(&current_command),
+                    timestamp,
+                });
                 current_command.clear();
             }
 
-            if let Some(cmd) = line.split_once(';').map(|x| x.1) {
+            if let Some((header, cmd)) = rest.split_once(';') {
+                // `: <start>:<elapsed>;<command>`
+                timestamp = header.split(':').next().and_then(|start| start.trim().parse().ok());
                 let trimmed = cmd.trim_end_matches('\\');
                 current_command.push_str(trimmed);
                 in_multiline = cmd.trim_end().ends_with('\\');
@@ -109,16 +174,25 @@ pub fn read_history(shell: Shell) -> Vec<String> {
             if in_multiline {
                 current_command.push('\n');
             }
+        } else if let Some(time) = line.strip_prefix('#').and_then(|time| time.parse().ok()) {
+            // Bash timestamp of the next command
+            timestamp = Some(time);
         } else {
             // Bash or Csh single-line history
             if !looks_corrupted(line) {
-                commands.push(line.to_string());
+                commands.push(HistoryEntry {
+                    command: line.to_string(),
+                    timestamp: timestamp.take(),
+                });
             }
         }
     }
 
     if !current_command.is_empty() {
-        commands.push(unescape_zsh(&current_command));
+        commands.push(HistoryEntry {
+            command: unescape_zsh(&current_command),
+            timestamp,
+        });
     }
 
     commands
@@ -179,22 +253,128 @@ pub fn hash_string(line: &str) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
+/// Returns the current Unix time in seconds.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
 pub fn prepare_string(text: &str) -> String {
     text.trim().replace("\\s+", "\\s")
 }
 
-/// Returns the path to the ~/.config/rushstr directory, creating it if needed.
-pub fn get_home_directory() -> anyhow::Result<String> {
-    let home = env::var("HOME")?;
-    let target: PathBuf = Path::new(&home).join(".config").join("rushstr");
+/// Environment variable that, when set, overrides every rushstr directory.
+///
+/// Handy for tests and CI, which should never touch the real home directory.
+pub const RUSHSTR_HOME: &str = "RUSHSTR_HOME";
+
+/// Returns the value of the environment variable `name` if it is set to an
+/// absolute path, as required by the XDG base directory specification.
+fn absolute_var(name: &str) -> Option<PathBuf> {
+    env::var_os(name).map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
+/// Resolves one of the rushstr base directories, creating it if needed.
+///
+/// The lookup order is:
+/// 1. `$RUSHSTR_HOME`
+/// 2. `$<xdg_var>/rushstr`
+/// 3. `$HOME/<fallback>/rushstr`
+fn resolve_directory(xdg_var: &str, fallback: &[&str]) -> anyhow::Result<PathBuf> {
+    let target = if let Some(home) = absolute_var(RUSHSTR_HOME) {
+        home
+    } else if let Some(base) = absolute_var(xdg_var) {
+        base.join("rushstr")
+    } else {
+        let home = PathBuf::from(env::var("HOME")?);
+        fallback.iter().fold(home, |path, part| path.join(part)).join("rushstr")
+    };
     fs::create_dir_all(&target)?;
-    Ok(target.to_string_lossy().into_owned())
+    Ok(target)
+}
+
+/// Returns the rushstr configuration directory, creating it if needed.
+///
+/// Defaults to `$XDG_CONFIG_HOME/rushstr` or `~/.config/rushstr`.
+pub fn get_config_directory() -> anyhow::Result<PathBuf> {
+    resolve_directory("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// Returns the rushstr data directory, creating it if needed.
+///
+/// `data_dir` (the `--data-dir` option) takes precedence over everything
+/// else; otherwise defaults to `$XDG_DATA_HOME/rushstr` or
+/// `~/.local/share/rushstr`.
+pub fn get_data_directory(data_dir: Option<&Path>) -> anyhow::Result<PathBuf> {
+    if let Some(target) = data_dir {
+        fs::create_dir_all(target)?;
+        return Ok(target.to_path_buf());
+    }
+    resolve_directory("XDG_DATA_HOME", &[".local", "share"])
+}
+
+/// Returns the rushstr state directory, creating it if needed.
+///
+/// Defaults to `$XDG_STATE_HOME/rushstr` or `~/.local/state/rushstr`.
+pub fn get_state_directory() -> anyhow::Result<PathBuf> {
+    resolve_directory("XDG_STATE_HOME", &[".local", "state"])
+}
+
+/// Returns the full path to the usage database.
+pub fn get_db_path(data_dir: Option<&Path>) -> anyhow::Result<PathBuf> {
+    Ok(get_data_directory(data_dir)?.join(RushstrFiles::DbName.val()))
+}
+
+/// Moves a database created by older releases in `~/.config/rushstr` to
+/// `target`.
+///
+/// Nothing happens if there is no legacy database, or if `target` already
+/// exists.
+fn migrate_legacy_db(target: &Path) -> anyhow::Result<()> {
+    let Ok(home) = env::var("HOME") else {
+        return Ok(());
+    };
+    let legacy = Path::new(&home)
+        .join(".config")
+        .join("rushstr")
+        .join(RushstrFiles::DbName.val());
+    if !legacy.exists() || target.exists() || legacy == target {
+        return Ok(());
+    }
+    if fs::rename(&legacy, target).is_err() {
+        // rename fails across file systems, fall back to copy and delete
+        copy_dir(&legacy, target)?;
+        fs::remove_dir_all(&legacy)?;
+    }
+    Ok(())
 }
 
-pub fn create_db() -> anyhow::Result<Db> {
-    let home = get_home_directory()?;
-    let db_name = RushstrFiles::DbName.val();
-    let target = format!("{home}/{db_name}");
+/// Recursively copies the directory `from` into `to`.
+fn copy_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let destination = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), destination)?;
+        }
+    }
+    Ok(())
+}
+
+/// Opens the usage database, creating it if needed.
+///
+/// When no directory override is in place, a database left behind by older
+/// releases in `~/.config/rushstr` is moved to the data directory first.
+pub fn create_db(data_dir: Option<&Path>) -> anyhow::Result<Db> {
+    let target = get_db_path(data_dir)?;
+    if data_dir.is_none() && absolute_var(RUSHSTR_HOME).is_none() {
+        migrate_legacy_db(&target)?;
+    }
     let db: Db = sled::open(target)?;
     Ok(db)
 }
@@ -228,29 +408,55 @@ pub fn configure_zsh_profile() -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn print_settings() -> anyhow::Result<()> {
-    let home = get_home_directory()?;
-    let db_name = RushstrFiles::DbName.val();
-    let target = format!("{home}/{db_name}");
-    println!("settings dir: {target}");
+pub fn print_settings(data_dir: Option<&Path>, config_file: &Path, config: &UserConfig) -> anyhow::Result<()> {
+    println!("config dir: {}", get_config_directory()?.display());
+    println!("config file: {}", config_file.display());
+    println!("database: {}", get_db_path(data_dir)?.display());
+    println!("state dir: {}", get_state_directory()?.display());
+    println!();
+    print!("{}", config.to_toml()?);
+    for conflict in config.keymap().conflicts() {
+        println!("# warning: {conflict}");
+    }
     Ok(())
 }
 
-pub fn delete_db() -> anyhow::Result<()> {
-    let home = get_home_directory()?;
-    let db_name = RushstrFiles::DbName.val();
-    let target = format!("{home}/{db_name}");
+pub fn delete_db(data_dir: Option<&Path>) -> anyhow::Result<()> {
+    let target = get_db_path(data_dir)?;
     fs::remove_dir_all(target)?;
     Ok(())
 }
 
+/// Forgets the items learned for queries, keeping everything else in the
+/// database.
+pub fn reset_learning(data_dir: Option<&Path>) -> anyhow::Result<()> {
+    create_db(data_dir)?.drop_tree(LEARNING_TREE)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use serial_test::serial;
-    use temp_env::with_var;
+    use temp_env::{with_var, with_vars};
 
     use super::*;
 
+    #[test]
+    fn test_parse_history_timestamps() {
+        let zsh = ": 1700000000:0;git status\n: 1700000100:3;echo a \\\\\nb\n: 1700000200:0;ls\n";
+        let entries = parse_history(zsh);
+        assert_eq!(entries[0].command, "git status");
+        assert_eq!(entries[0].timestamp, Some(1_700_000_000));
+        assert_eq!(entries[1].command, "echo a \nb");
+        assert_eq!(entries[1].timestamp, Some(1_700_000_100));
+
+        let bash = "#1700000000\nmake\nls\n";
+        let entries = parse_history(bash);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp, Some(1_700_000_000));
+        assert_eq!(entries[1].timestamp, None);
+    }
+
     #[test]
     #[serial]
     fn test_detect_zsh() {
@@ -290,4 +496,107 @@ mod tests {
             assert_eq!(detect_shell(), Shell::Unknown);
         });
     }
+
+    /// Returns an empty directory under the system temp dir for a test.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rushstr-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    #[serial]
+    fn test_rushstr_home_overrides_all() {
+        let home = scratch_dir("override");
+        with_vars(
+            [
+                (RUSHSTR_HOME, Some(home.to_str().unwrap())),
+                ("XDG_CONFIG_HOME", Some("/nonexistent/config")),
+            ],
+            || {
+                assert_eq!(get_config_directory().unwrap(), home);
+                assert_eq!(get_data_directory(None).unwrap(), home);
+                assert_eq!(get_state_directory().unwrap(), home);
+            },
+        );
+        fs::remove_dir_all(home).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_xdg_directories() {
+        let base = scratch_dir("xdg");
+        let config = base.join("config");
+        let data = base.join("data");
+        let state = base.join("state");
+        with_vars(
+            [
+                (RUSHSTR_HOME, None),
+                ("XDG_CONFIG_HOME", Some(config.to_str().unwrap())),
+                ("XDG_DATA_HOME", Some(data.to_str().unwrap())),
+                ("XDG_STATE_HOME", Some(state.to_str().unwrap())),
+            ],
+            || {
+                assert_eq!(get_config_directory().unwrap(), config.join("rushstr"));
+                assert_eq!(get_data_directory(None).unwrap(), data.join("rushstr"));
+                assert_eq!(get_state_directory().unwrap(), state.join("rushstr"));
+            },
+        );
+        fs::remove_dir_all(base).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_home_fallback_ignores_relative_xdg() {
+        let home = scratch_dir("fallback");
+        with_vars(
+            [
+                (RUSHSTR_HOME, None),
+                ("HOME", Some(home.to_str().unwrap())),
+                ("XDG_CONFIG_HOME", Some("relative/config")),
+                ("XDG_DATA_HOME", None),
+            ],
+            || {
+                assert_eq!(get_config_directory().unwrap(), home.join(".config/rushstr"));
+                assert_eq!(get_data_directory(None).unwrap(), home.join(".local/share/rushstr"));
+            },
+        );
+        fs::remove_dir_all(home).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_data_dir_option_wins() {
+        let home = scratch_dir("home-option");
+        let data = scratch_dir("data-option");
+        with_var(RUSHSTR_HOME, Some(home.to_str().unwrap()), || {
+            assert_eq!(get_data_directory(Some(&data)).unwrap(), data);
+            assert_eq!(get_db_path(Some(&data)).unwrap(), data.join("rushstr.db"));
+        });
+        assert!(!home.exists());
+        fs::remove_dir_all(data).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_legacy_db_is_migrated() {
+        let home = scratch_dir("migrate");
+        let legacy = home.join(".config/rushstr/rushstr.db");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("conf"), "legacy").unwrap();
+        with_vars(
+            [
+                (RUSHSTR_HOME, None),
+                ("HOME", Some(home.to_str().unwrap())),
+                ("XDG_DATA_HOME", None),
+            ],
+            || {
+                let target = get_db_path(None).unwrap();
+                migrate_legacy_db(&target).unwrap();
+                assert!(!legacy.exists());
+                assert_eq!(fs::read_to_string(target.join("conf")).unwrap(), "legacy");
+            },
+        );
+        fs::remove_dir_all(home).unwrap();
+    }
 }
@@ -1,35 +1,42 @@
 use ratatui::Frame;
 use ratatui::layout::Rect;
-use ratatui::prelude::{Color, Line, Modifier, Span, Style};
+use ratatui::prelude::{Line, Span, Style};
 use ratatui::widgets::Paragraph;
-use rushstr_core::{HItem, HLines, Store};
+use rushstr_core::{Action, HItem, HLines, Keymap, Store};
 
-use crate::{UiState, hindex_to_hlines};
+use crate::{Theme, UiState, hindex_to_hlines};
 
 pub struct InfoBar<'f> {
     items: &'f [HItem],
     store: &'f Store,
     ui_state: &'f UiState,
+    keymap: &'f Keymap,
+    theme: &'f Theme,
     layout: &'f [Rect],
 }
 
 impl<'f> InfoBar<'f> {
-    pub fn new(items: &'f [HItem], ui_state: &'f UiState, store: &'f Store, layout: &'f [Rect]) -> Self {
+    pub fn new(
+        items: &'f [HItem],
+        ui_state: &'f UiState,
+        store: &'f Store,
+        keymap: &'f Keymap,
+        theme: &'f Theme,
+        layout: &'f [Rect],
+    ) -> Self {
         Self {
             items,
             ui_state,
             store,
+            keymap,
+            theme,
             layout,
         }
     }
 
     pub fn render(&self, frame: &mut Frame) {
-        let case = if self.ui_state.search_options.is_case_insensitive() {
-            "insensitive"
-        } else {
-            "sensitive"
-        };
-        let height = frame.area().height as usize - 2;
+        let case = self.ui_state.search_options.case_mode.to_str();
+        let height = (frame.area().height as usize).saturating_sub(2);
         let matching = self.ui_state.search_options.search_type.to_str();
         let mut spans = self.build_info_bar(case, matching, height);
 
@@ -44,65 +51,110 @@ impl<'f> InfoBar<'f> {
         }
         spans.extend(padded);
 
-        let paragraph = Paragraph::new(
-            Line::from(spans).style(
-                Style::default()
-                    .bg(Color::Black)
-                    .fg(Color::White)
-                    .add_modifier(Modifier::BOLD),
-            ),
-        );
+        let paragraph = Paragraph::new(Line::from(spans).style(self.theme.info_bar));
 
         frame.render_widget(paragraph, self.layout[1]);
     }
 
-    fn build_info_bar(&self, case: &str, matching: &str, height: HLines) -> Vec<Span> {
+    fn build_info_bar(&self, case: &str, matching: &str, height: HLines) -> Vec<Span<'_>> {
         if self.ui_state.debug {
             return self.build_debug_info_bar(case, matching, height);
         }
         self.base_info_base(case, matching)
     }
 
-    fn base_info_base(&self, case: &str, matching: &str) -> Vec<Span> {
+    fn base_info_base(&self, case: &str, matching: &str) -> Vec<Span<'_>> {
         let store_total = self.store.total().ok().unwrap_or(0).to_string();
         let store_favorites = self.store.favorites().ok().unwrap_or(0).to_string();
-        let favs_style = if self.ui_state.search_options.favorites {
-            Style::default().fg(Color::Cyan)
+        let favs_style = if self.ui_state.search_options.favorites_only() {
+            self.theme.info_active
         } else {
             Style::default()
         };
-        vec![
+        let mut spans = vec![
             Span::raw("HISTORY -"),
-            Span::raw(" (⭐C-x)"),
-            Span::styled(" (C-f)", favs_style),
-            Span::raw(" (C-t)"),
+            Span::raw(self.hint(Action::ToggleFavorite, "⭐")),
+            Span::styled(self.hint(Action::FavoritesOnly, ""), favs_style),
+            Span::raw(self.hint(Action::CycleMode, "")),
+            Span::raw(self.hint(Action::CycleRanking, "")),
+            Span::raw(self.hint(Action::CycleCase, "")),
+            Span::raw(self.hint(Action::ToggleScope, "")),
+            Span::raw(self.hint(Action::RecentSearches, "")),
             Span::raw(" match:"),
-            Span::styled(format!("{matching:<15}"), Style::default().fg(Color::Blue)),
+            Span::styled(format!("{matching:<15}"), self.theme.info_value),
+            Span::raw("rank:"),
+            Span::styled(
+                format!("{:<14}", self.ui_state.search_options.ranking.to_str()),
+                self.theme.info_value,
+            ),
             Span::raw("case:"),
-            Span::styled(format!("{case:<15}"), Style::default().fg(Color::Blue)),
+            Span::styled(format!("{case:<12}"), self.theme.info_value),
+            Span::raw("text:"),
+            Span::styled(
+                format!("{:<8}", self.ui_state.search_options.scope.to_str()),
+                self.theme.info_value,
+            ),
             Span::raw(" - "),
-            Span::styled(self.items.len().to_string(), Style::default().fg(Color::Blue)),
+            Span::styled(self.items.len().to_string(), self.theme.info_value),
             Span::raw("/"),
-            Span::styled(store_total, Style::default().fg(Color::Blue)),
+            Span::styled(store_total, self.theme.info_value),
             Span::raw("/"),
-            Span::styled(store_favorites, Style::default().fg(Color::Blue)),
-        ]
+            Span::styled(store_favorites, self.theme.info_value),
+        ];
+        if !self.ui_state.marked.is_empty() {
+            spans.push(Span::styled(
+                format!(" ✔{} marked", self.ui_state.marked.len()),
+                self.theme.marked,
+            ));
+        }
+        if let Some(error) = &self.ui_state.query_error {
+            spans.push(Span::styled(format!(" ⚠ {}", error.message), self.theme.error));
+        }
+        if let Some(notice) = &self.ui_state.notice {
+            spans.push(Span::styled(format!(" ⚠ {notice}"), self.theme.notice));
+        }
+        spans
+    }
+
+    /// Returns the hint of the first key bound to `action`, e.g. ` (C-t)`,
+    /// or nothing when no key runs it.
+    fn hint(&self, action: Action, prefix: &str) -> String {
+        self.keymap
+            .keys(action)
+            .first()
+            .map(|key| format!(" ({prefix}{})", short_key_name(key)))
+            .unwrap_or_default()
     }
 
-    fn build_debug_info_bar(&self, case: &str, matching: &str, height: HLines) -> Vec<Span> {
+    fn build_debug_info_bar(&self, case: &str, matching: &str, height: HLines) -> Vec<Span<'_>> {
         let h_to_selected = hindex_to_hlines(self.items, self.ui_state.selected);
         let mut parts = self.base_info_base(case, matching);
         let debug = vec![
             Span::raw(" 🐛 height:"),
-            Span::styled(format!("{height:<4}"), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{height:<4}"), self.theme.notice),
             Span::raw("selected:"),
-            Span::styled(format!("{:<4}", self.ui_state.selected), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:<4}", self.ui_state.selected), self.theme.notice),
             Span::raw("offset:"),
-            Span::styled(format!("{:<4}", self.ui_state.offset), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:<4}", self.ui_state.offset), self.theme.notice),
             Span::raw("h_to_selected:"),
-            Span::styled(format!("{h_to_selected:<4}"), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{h_to_selected:<4}"), self.theme.notice),
         ];
         parts.extend(debug);
         parts
     }
 }
+
+/// Shortens the modifiers of a key name emacs style: `ctrl-alt-x` becomes
+/// `C-M-x`.
+pub(crate) fn short_key_name(key: &str) -> String {
+    let mut rest = key;
+    let mut short = String::new();
+    for (modifier, abbreviation) in [("ctrl-", "C-"), ("alt-", "M-"), ("shift-", "S-")] {
+        if let Some(stripped) = rest.strip_prefix(modifier) {
+            short.push_str(abbreviation);
+            rest = stripped;
+        }
+    }
+    short.push_str(rest);
+    short
+}
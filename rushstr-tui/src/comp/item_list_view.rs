@@ -1,31 +1,46 @@
-use std::collections::HashSet;
+use std::ops::Range;
 
 use ratatui::Frame;
 use ratatui::layout::Rect;
-use ratatui::prelude::{Color, Line, Modifier, Span, Style};
+use ratatui::prelude::{Line, Span, Style};
 use ratatui::text::Text;
 use ratatui::widgets::{Block, Borders, List, ListItem};
-use rushstr_core::{HIndex, HItem, prepare_string};
+use rushstr_core::{HIndex, HItem, Highlighter, SyntaxToken, tokenize_shell};
+use unicode_width::UnicodeWidthChar;
 
-use crate::UiState;
+use crate::{Theme, UiState};
+
+/// Width of the gutter marking the items marked with Tab.
+const GUTTER_WIDTH: u16 = 2;
 
 pub struct ItemListView<'f> {
     items: &'f [HItem],
     ui_state: &'f UiState,
+    highlighter: &'f Highlighter,
+    theme: &'f Theme,
     layout: &'f [Rect],
 }
 
 impl<'f> ItemListView<'f> {
-    pub fn new(items: &'f [HItem], ui_state: &'f UiState, layout: &'f [Rect]) -> Self {
+    pub fn new(
+        items: &'f [HItem],
+        ui_state: &'f UiState,
+        highlighter: &'f Highlighter,
+        theme: &'f Theme,
+        layout: &'f [Rect],
+    ) -> Self {
         Self {
             items,
             ui_state,
+            highlighter,
+            theme,
             layout,
         }
     }
 
     pub fn render(&self, frame: &mut Frame) {
         let height = self.layout[2].height as usize;
+        let width = content_width(self.ui_state, self.layout[2].width);
         let items = self
             .items
             .iter()
@@ -37,7 +52,10 @@ impl<'f> ItemListView<'f> {
         let list_items: Vec<ListItem> = items
             .into_iter()
             .enumerate()
-            .map(|(index, item)| format_item(index, item, self.ui_state))
+            .map(|(index, item)| {
+                let line_ranges = self.highlighter.line_ranges(&item);
+                format_item(index, item, self.ui_state, &line_ranges, width, self.theme)
+            })
             .collect();
 
         let list = List::new(list_items).block(Block::default().borders(Borders::NONE));
@@ -51,114 +69,140 @@ impl<'f> ItemListView<'f> {
 ///
 /// This function handles:
 /// - Highlighting the currently selected item.
-/// - Applying text highlighting for search matches on each line of the command.
-/// - Building a styled `ListItem` from multi-line commands.
+/// - Colouring the shell syntax of the command, except on the selected row
+///   whose colours stay readable on its background.
+/// - Highlighting the characters matched by the search on each line of the
+///   command, over the syntax colours.
+/// - Marking the items marked with Tab in a gutter, shown while any item is
+///   marked.
+/// - Building a styled `ListItem` from multi-line commands, wrapping lines
+///   longer than `width` onto as many rows as `item_rows` counts.
 ///
 /// # Arguments
 ///
 /// * `index` - The index of the item in the visible list.
 /// * `item` - The `HItem` to format, containing the command lines.
 /// * `ui_state` - The current UI state, including selection and search options.
+/// * `line_ranges` - The matched character ranges of every command line.
+/// * `width` - The cells available to the command, see `content_width`.
+/// * `theme` - The styles of the selection, the matches and the syntax.
 ///
 /// # Returns
 ///
 /// A `ListItem` that is:
 /// - Highlighted if selected.
-/// - Styled with `Span` highlights for matched tokens if a search input exists.
+/// - Styled with `Span` highlights for the matched ranges.
 /// - Composed of potentially multiple lines, preserving command formatting.
-pub(crate) fn format_item(index: HIndex, item: HItem, ui_state: &UiState) -> ListItem {
-    let selected = ui_state.selected - ui_state.offset;
-    let style = if index == selected {
-        Style::default()
-            .bg(Color::LightYellow)
-            .fg(Color::Black)
-            .add_modifier(Modifier::BOLD)
-    } else {
-        Style::default()
-    };
+pub(crate) fn format_item<'a>(
+    index: HIndex,
+    item: HItem,
+    ui_state: &UiState,
+    line_ranges: &[Vec<Range<usize>>],
+    width: u16,
+    theme: &Theme,
+) -> ListItem<'a> {
+    let selected = index == ui_state.selected - ui_state.offset;
+    let style = if selected { theme.selection } else { Style::default() };
 
-    let input = &ui_state.search_options.input;
+    let lines = item.command_lines();
+    let tokens = tokenize_shell(&lines);
+    let marked = ui_state.is_marked(&item.id());
     let mut cmd_lines = Vec::new();
-    let in_lines = item.command_lines();
-    for line in in_lines {
-        let o_line = if !input.is_empty() {
-            let case_insensitive = ui_state.search_options.is_case_insensitive();
-            let c_text = prepare_string(input);
-            let spans = match_tokens(&line, &c_text, case_insensitive);
-            Line::from(spans)
+    for (line_index, line) in lines.iter().enumerate() {
+        let ranges = line_ranges.get(line_index).map_or(&[][..], Vec::as_slice);
+        let spans = if selected {
+            match_spans(line, ranges, theme)
         } else {
-            Line::raw(line)
+            styled_spans(line, &tokens[line_index], ranges, theme)
         };
-        cmd_lines.push(o_line)
+        for (row, mut wrapped) in wrap_spans(spans, width).into_iter().enumerate() {
+            if !ui_state.marked.is_empty() {
+                let gutter = if marked && line_index == 0 && row == 0 {
+                    "✔ "
+                } else {
+                    "  "
+                };
+                wrapped.spans.insert(0, Span::styled(gutter, theme.marked));
+            }
+            cmd_lines.push(wrapped)
+        }
     }
     let text = Text::from(cmd_lines);
 
     ListItem::new(text).style(style)
 }
 
-/// Creates a set of unique characters (tokens) from the input string.
-///
-/// If `case_insensitive` is true, both lowercase and uppercase variants
-/// of each character are included.
+/// Returns the cells a list `width` cells wide leaves to the commands: the
+/// gutter takes some while any item is marked.
+pub(crate) fn content_width(ui_state: &UiState, width: u16) -> u16 {
+    if ui_state.marked.is_empty() {
+        width
+    } else {
+        width.saturating_sub(GUTTER_WIDTH)
+    }
+}
+
+/// Wraps styled `spans` into rows at most `width` cells wide, breaking
+/// where `line_rows` does so both agree on the height of an item.
 ///
 /// # Arguments
 ///
-/// * `input` - The input string to tokenize.
-/// * `case_insensitive` - Whether to treat characters case-insensitively.
+/// * `spans` - The styled spans of one command line.
+/// * `width` - The cells available on every row.
 ///
 /// # Returns
 ///
-/// A `HashSet<char>` containing the unique token characters.
-pub(crate) fn create_tokens(input: &str, case_insensitive: bool) -> HashSet<char> {
-    let mut tokens = HashSet::new();
-
-    for token in input.split_whitespace().filter(|t| !t.is_empty()) {
-        for c in token.chars() {
-            if case_insensitive {
-                tokens.extend(c.to_lowercase());
-                tokens.extend(c.to_uppercase());
-            } else {
-                tokens.insert(c);
+/// At least one `Line`, keeping the style of every span.
+pub(crate) fn wrap_spans(spans: Vec<Span<'_>>, width: u16) -> Vec<Line<'static>> {
+    let mut rows = vec![Line::default()];
+    let mut used = 0;
+    for span in spans {
+        let mut chunk = String::new();
+        for c in span.content.chars() {
+            let cells = c.width().unwrap_or(0);
+            if used > 0 && used + cells > width as usize {
+                let full = std::mem::take(&mut chunk);
+                if !full.is_empty() {
+                    rows.last_mut().unwrap().spans.push(Span::styled(full, span.style));
+                }
+                rows.push(Line::default());
+                used = 0;
             }
+            chunk.push(c);
+            used += cells;
+        }
+        if !chunk.is_empty() {
+            rows.last_mut().unwrap().spans.push(Span::styled(chunk, span.style));
         }
     }
-
-    tokens
+    rows
 }
 
-/// Splits an input string (`item`) into segments, tagging each segment with a
-/// boolean that indicates whether it contains characters present in the search
-/// input.
-///
-/// The segmentation is based on whether each character is part of the search
-/// tokens generated from `input`, considering case sensitivity.
+/// Splits `line` into segments, tagging each segment with a boolean that
+/// indicates whether it falls inside one of the matched `ranges`.
 ///
 /// # Arguments
 ///
-/// * `item` - The string to analyze and split into segments.
-/// * `input` - The user-provided search input used to generate matching tokens.
-/// * `case_insensitive` - If `true`, the matching ignores character casing.
+/// * `line` - The string to split into segments.
+/// * `ranges` - Sorted, non-overlapping character ranges of `line`.
 ///
 /// # Returns
 ///
 /// A `Vec<(String, bool)>` where each tuple contains:
-/// - a substring of `item`
-/// - a boolean indicating whether that substring matches any of the search
-///   tokens
-pub(crate) fn token_finder(item: &str, input: &str, case_insensitive: bool) -> Vec<(String, bool)> {
-    let tokens = create_tokens(input, case_insensitive);
+/// - a substring of `line`
+/// - a boolean indicating whether that substring was matched
+pub(crate) fn segments(line: &str, ranges: &[Range<usize>]) -> Vec<(String, bool)> {
     let mut result = Vec::new();
     let mut current = String::new();
     let mut current_flag: Option<bool> = None;
 
-    for char in item.chars() {
-        let flag = tokens.contains(&char);
+    for (index, char) in line.chars().enumerate() {
+        let flag = ranges.iter().any(|range| range.contains(&index));
         match current_flag {
             Some(f) if f != flag => {
                 result.push((current.clone(), f));
                 current.clear();
             },
-            None => {},
             _ => {},
         }
         current.push(char);
@@ -172,30 +216,25 @@ pub(crate) fn token_finder(item: &str, input: &str, case_insensitive: bool) -> V
     result
 }
 
-/// Converts a string (`item`) into a vector of styled spans, where each span
-/// highlights whether its characters match the search input.
-///
-/// This is typically used to visually highlight matching characters (e.g., in a
-/// TUI), with matching segments rendered in bold red text and non-matching
-/// segments unstyled.
+/// Converts `line` into a vector of styled spans, rendering the matched
+/// `ranges` in the match style of `theme` and the rest unstyled.
 ///
 /// # Arguments
 ///
-/// * `item` - The string to be analyzed and segmented into styled spans.
-/// * `input` - The user input string used to determine which characters to
+/// * `line` - The string to be segmented into styled spans.
+/// * `ranges` - Sorted, non-overlapping character ranges of `line` to
 ///   highlight.
-/// * `case_insensitive` - Whether to perform case-insensitive matching.
+/// * `theme` - The styles of the matches.
 ///
 /// # Returns
 ///
 /// A `Vec<Span>` representing the original string broken into styled and
-/// unstyled parts, depending on character matches against the input.
-pub(crate) fn match_tokens<'a>(item: &str, input: &str, case_insensitive: bool) -> Vec<Span<'a>> {
+/// unstyled parts.
+pub(crate) fn match_spans<'a>(line: &str, ranges: &[Range<usize>], theme: &Theme) -> Vec<Span<'a>> {
     let mut spans = Vec::new();
-    let styled_flags = token_finder(item, input, case_insensitive);
-    for (str, red) in styled_flags {
-        let span = if red {
-            Span::styled(str, Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+    for (str, matched) in segments(line, ranges) {
+        let span = if matched {
+            Span::styled(str, theme.matched)
         } else {
             Span::raw(str)
         };
@@ -203,3 +242,39 @@ pub(crate) fn match_tokens<'a>(item: &str, input: &str, case_insensitive: bool)
     }
     spans
 }
+
+/// Converts `line` into styled spans, colouring its shell syntax `tokens`
+/// and rendering the matched `ranges` over them as `match_spans` does.
+///
+/// # Arguments
+///
+/// * `line` - The command line to style.
+/// * `tokens` - The syntax tokens of `line`, as returned by `tokenize_shell`.
+/// * `ranges` - Sorted, non-overlapping character ranges of `line` to
+///   highlight.
+/// * `theme` - The styles of the syntax and the matches.
+pub(crate) fn styled_spans<'a>(
+    line: &str,
+    tokens: &[SyntaxToken],
+    ranges: &[Range<usize>],
+    theme: &Theme,
+) -> Vec<Span<'a>> {
+    let mut spans: Vec<Span> = Vec::new();
+    let chars = segments(line, ranges)
+        .into_iter()
+        .flat_map(|(segment, matched)| segment.chars().map(move |char| (char, matched)).collect::<Vec<_>>());
+    for (index, (char, matched)) in chars.enumerate() {
+        let mut style = tokens
+            .iter()
+            .find(|token| token.range.contains(&index))
+            .map_or_else(Style::default, |token| theme.syntax(token.kind));
+        if matched {
+            style = style.patch(theme.matched);
+        }
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push(char),
+            _ => spans.push(Span::styled(char.to_string(), style)),
+        }
+    }
+    spans
+}
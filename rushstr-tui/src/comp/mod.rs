@@ -1,5 +1,7 @@
 pub mod info_bar;
 pub mod item_list_view;
+pub mod preview_view;
+pub mod query_list_view;
 pub mod search_box;
 #[cfg(test)]
 mod tests;
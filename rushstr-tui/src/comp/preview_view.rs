@@ -0,0 +1,114 @@
+use std::ops::Range;
+
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::prelude::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use rushstr_core::{Action, HItem, Highlighter, Keymap, now_secs, tokenize_shell};
+
+use crate::Theme;
+use crate::comp::info_bar::short_key_name;
+use crate::comp::item_list_view::styled_spans;
+use crate::utils::format::age;
+
+/// The whole selected command, wrapped, followed by its metadata.
+pub struct PreviewView<'f> {
+    item: Option<&'f HItem>,
+    highlighter: &'f Highlighter,
+    keymap: &'f Keymap,
+    theme: &'f Theme,
+    area: Rect,
+}
+
+impl<'f> PreviewView<'f> {
+    pub fn new(
+        item: Option<&'f HItem>,
+        highlighter: &'f Highlighter,
+        keymap: &'f Keymap,
+        theme: &'f Theme,
+        area: Rect,
+    ) -> Self {
+        Self {
+            item,
+            highlighter,
+            keymap,
+            theme,
+            area,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let lines = match self.item {
+            Some(item) => preview_lines(item, &self.highlighter.line_ranges(item), now_secs(), self.theme),
+            None => Vec::new(),
+        };
+        let preview = Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(preview_title(self.keymap)))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(preview, self.area);
+    }
+}
+
+/// Returns the title of the preview, with the key hiding it when one is
+/// bound.
+pub(crate) fn preview_title(keymap: &Keymap) -> String {
+    match keymap.keys(Action::TogglePreview).first() {
+        Some(key) => format!(" preview ({}) ", short_key_name(key)),
+        None => " preview ".to_string(),
+    }
+}
+
+/// Builds the lines of the preview of `item`: every line of the command with
+/// its shell syntax coloured and the matched `line_ranges` highlighted, then
+/// its metadata, `now` being the current Unix time in seconds.
+pub(crate) fn preview_lines<'a>(
+    item: &HItem,
+    line_ranges: &[Vec<Range<usize>>],
+    now: u64,
+    theme: &Theme,
+) -> Vec<Line<'a>> {
+    let command_lines = item.command_lines();
+    let tokens = tokenize_shell(&command_lines);
+    let mut lines: Vec<Line> = command_lines
+        .iter()
+        .zip(&tokens)
+        .enumerate()
+        .map(|(index, (line, tokens))| {
+            Line::from(styled_spans(
+                line,
+                tokens,
+                line_ranges.get(index).map_or(&[], Vec::as_slice),
+                theme,
+            ))
+        })
+        .collect();
+    lines.push(Line::raw(""));
+
+    let favorite = if item.is_fav() { "yes ⭐" } else { "no" };
+    lines.push(meta_line("hits", item.hits().to_string(), theme));
+    lines.push(meta_line("favorite", favorite.to_string(), theme));
+    let tags = if item.tags().is_empty() {
+        "-".to_string()
+    } else {
+        item.tags().join(", ")
+    };
+    lines.push(meta_line("tags", tags, theme));
+    let last_run = match item.last_run() {
+        0 => "unknown".to_string(),
+        time => age(now.saturating_sub(time)),
+    };
+    lines.push(meta_line("last run", last_run, theme));
+    if let Some(dir) = item.dir() {
+        lines.push(meta_line("dir", dir.to_string(), theme));
+    }
+    if let Some(exit) = item.exit() {
+        lines.push(meta_line("exit", exit.to_string(), theme));
+    }
+    lines.push(meta_line("source", item.source().unwrap_or("-").to_string(), theme));
+    lines
+}
+
+/// Formats a metadata entry, the label dimmed.
+fn meta_line<'a>(label: &str, value: String, theme: &Theme) -> Line<'a> {
+    Line::from(vec![Span::styled(format!("{label:<9}"), theme.dim), Span::raw(value)])
+}
@@ -0,0 +1,82 @@
+use ratatui::Frame;
+use ratatui::layout::Rect;
+use ratatui::prelude::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState};
+use rushstr_core::{Action, Keymap, RecentQuery, now_secs};
+
+use crate::comp::info_bar::short_key_name;
+use crate::utils::format::age;
+use crate::{Theme, UiState};
+
+/// The list of recent searches, drawn over the results while it is open.
+pub struct QueryListView<'f> {
+    ui_state: &'f UiState,
+    keymap: &'f Keymap,
+    theme: &'f Theme,
+    layout: &'f [Rect],
+}
+
+impl<'f> QueryListView<'f> {
+    pub fn new(ui_state: &'f UiState, keymap: &'f Keymap, theme: &'f Theme, layout: &'f [Rect]) -> Self {
+        Self {
+            ui_state,
+            keymap,
+            theme,
+            layout,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let Some(selected) = self.ui_state.query_list else {
+            return;
+        };
+        let now = now_secs();
+        let list_items: Vec<ListItem> = self
+            .ui_state
+            .recent_queries
+            .iter()
+            .map(|query| ListItem::new(query_line(query, now, self.theme)))
+            .collect();
+        let list = List::new(list_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(query_list_title(self.keymap)),
+            )
+            .highlight_style(self.theme.selection);
+        let mut state = ListState::default().with_selected(Some(selected));
+
+        frame.render_widget(Clear, self.layout[2]);
+        frame.render_stateful_widget(list, self.layout[2], &mut state);
+    }
+}
+
+/// Returns the title of the list of recent searches, with the keys of
+/// `accept` and `cancel` when they are bound.
+pub(crate) fn query_list_title(keymap: &Keymap) -> String {
+    let hints: Vec<String> = [(Action::Accept, "search again"), (Action::Cancel, "close")]
+        .into_iter()
+        .filter_map(|(action, what)| {
+            keymap
+                .keys(action)
+                .first()
+                .map(|key| format!("{}: {what}", short_key_name(key)))
+        })
+        .collect();
+    if hints.is_empty() {
+        " recent searches ".to_string()
+    } else {
+        format!(" recent searches - {} ", hints.join(", "))
+    }
+}
+
+/// Formats a recent search as its input followed by its search type and
+/// age, `now` being the current Unix time in seconds.
+pub(crate) fn query_line<'a>(query: &'a RecentQuery, now: u64, theme: &Theme) -> Line<'a> {
+    let dim = theme.dim;
+    Line::from(vec![
+        Span::raw(query.input.as_str()),
+        Span::styled(format!("  {}", query.search_type), dim),
+        Span::styled(format!(" {}", age(now.saturating_sub(query.time))), dim),
+    ])
+}
@@ -1,35 +1,116 @@
 use ratatui::Frame;
 use ratatui::layout::{Position, Rect};
-use ratatui::prelude::{Color, Line, Span, Style};
+use ratatui::prelude::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph};
+use unicode_width::UnicodeWidthStr;
 
-use crate::UiState;
+use crate::{Prompt, Theme, UiState};
 
 pub struct SearchBox<'f> {
     search_options: &'f UiState,
+    theme: &'f Theme,
     layout: &'f [Rect],
 }
 
 impl<'f> SearchBox<'f> {
-    pub fn new(search_options: &'f UiState, layout: &'f [Rect]) -> Self {
-        Self { search_options, layout }
+    pub fn new(search_options: &'f UiState, theme: &'f Theme, layout: &'f [Rect]) -> Self {
+        Self {
+            search_options,
+            theme,
+            layout,
+        }
     }
 
     pub fn render(&self, frame: &mut Frame) {
-        let spans = vec![
-            Span::styled("🦀:search:", Style::default().fg(Color::Green)),
-            Span::styled("> ", Style::default().fg(Color::Cyan)),
-            Span::styled(&self.search_options.search_options.input, Style::default().fg(Color::Green)),
+        if let Some(prompt) = &self.search_options.prompt {
+            let spans = prompt_spans(prompt, self.search_options.marked.len(), self.theme);
+            let width: usize = spans.iter().map(|s| s.content.width()).sum();
+            frame.render_widget(Paragraph::new(Line::from(spans)), self.layout[0]);
+            frame.set_cursor_position(Position::new(self.layout[0].x + width as u16, self.layout[0].y));
+            return;
+        }
+        let input = &self.search_options.search_options.input;
+        let mut spans = vec![
+            Span::styled("🦀:search:", self.theme.prompt),
+            Span::styled("> ", self.theme.prompt_arrow),
         ];
-        let line = Line::from(spans.clone());
+        // Show the cursor inside the search bar
+        let prompt_width: usize = spans.iter().map(|s| s.content.width()).sum();
+        let cursor = self.search_options.editor.cursor(input);
+        let error_position = self
+            .search_options
+            .query_error
+            .as_ref()
+            .and_then(|error| error.position);
+        spans.extend(input_spans(input, error_position, self.theme));
+        let line = Line::from(spans);
 
         let search = Paragraph::new(line).block(Block::default().borders(Borders::NONE));
-        // Show the cursor inside the search bar
-        let x_offset: usize = spans.iter().map(|s| s.content.chars().count()).sum();
-        let cursor_x = self.layout[0].x + x_offset as u16 + 1; // +1 to account for left border
+        let cursor_x = self.layout[0].x + cursor_column(prompt_width, input, cursor) as u16;
         let cursor_y = self.layout[0].y;
         let position = Position::new(cursor_x, cursor_y);
         frame.render_widget(search, self.layout[0]);
         frame.set_cursor_position(position);
     }
 }
+
+/// Builds the line asking `prompt` in place of the search box, for the
+/// `marked` items or the selected item when none is marked.
+pub(crate) fn prompt_spans<'a>(prompt: &'a Prompt, marked: usize, theme: &Theme) -> Vec<Span<'a>> {
+    let targets = match marked {
+        0 => "the selected command".to_string(),
+        1 => "1 marked command".to_string(),
+        count => format!("{count} marked commands"),
+    };
+    match prompt {
+        Prompt::Tag(tag) => {
+            vec![
+                Span::styled(format!("🦀:tag {targets}:"), theme.prompt),
+                Span::styled("> ", theme.prompt_arrow),
+                Span::styled(tag.as_str(), theme.prompt),
+            ]
+        },
+        Prompt::Delete => {
+            vec![
+                Span::styled(format!("🦀:delete {targets}?"), theme.error),
+                Span::styled(" (y/n) ", theme.prompt_arrow),
+            ]
+        },
+    }
+}
+
+/// Returns the column of the cursor placed before the character `cursor` of
+/// `input`, in terminal cells: wide characters such as CJK or emoji take
+/// two.
+pub(crate) fn cursor_column(prompt_width: usize, input: &str, cursor: usize) -> usize {
+    let before: String = input.chars().take(cursor).collect();
+    prompt_width + before.width()
+}
+
+/// Splits `input` into spans, marking the character at `error_position` as
+/// invalid, or a trailing marker when the error is at the end of the input.
+pub(crate) fn input_spans<'a>(input: &'a str, error_position: Option<usize>, theme: &Theme) -> Vec<Span<'a>> {
+    let style = theme.prompt;
+    let error_style = theme.invalid;
+    let Some(position) = error_position else {
+        return vec![Span::styled(input, style)];
+    };
+    let start = input
+        .char_indices()
+        .nth(position)
+        .map(|(index, _)| index)
+        .unwrap_or(input.len());
+    let end = input[start..]
+        .chars()
+        .next()
+        .map(|c| start + c.len_utf8())
+        .unwrap_or(start);
+    let mut spans = vec![Span::styled(&input[..start], style)];
+    if start == end {
+        spans.push(Span::styled(" ", error_style));
+    } else {
+        spans.push(Span::styled(&input[start..end], error_style));
+        spans.push(Span::styled(&input[end..], style));
+    }
+    spans
+}
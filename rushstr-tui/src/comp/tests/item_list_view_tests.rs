@@ -1,52 +1,74 @@
-use crate::comp::item_list_view::{create_tokens, match_tokens, token_finder};
+#![allow(clippy::single_range_in_vec_init)]
 
-#[test]
-pub fn test_create_tokens1() {
-    let text = "a o";
-    let out = create_tokens(text, false);
-    assert_eq!(out.len(), 2);
-
-    let text = "a b    c";
-    let out = create_tokens(text, false);
-    assert_eq!(out.len(), 3);
-
-    let text = "a o a";
-    let out = create_tokens(text, false);
-    assert_eq!(out.len(), 2);
+use ratatui::prelude::Style;
+use rushstr_core::{SyntaxKind, tokenize_shell};
 
-    let text = "a o A";
-    let out = create_tokens(text, false);
-    assert_eq!(out.len(), 3);
+use crate::Theme;
+use crate::comp::item_list_view::{match_spans, segments, styled_spans};
 
-    let text = "mama anorta";
-    let out = create_tokens(text, false);
-    assert_eq!(out.len(), 6);
+#[test]
+pub fn test_segments_no_ranges() {
+    let out = segments("frederick has a kangaroo", &[]);
+    assert_eq!(out, vec![("frederick has a kangaroo".to_string(), false)]);
 }
 
 #[test]
-pub fn test_token_finder1() {
+pub fn test_segments1() {
     let item = "frederick has a kangaroo";
-    let text = "f h k";
-    let out = token_finder(item, text, false);
-    assert_eq!(out.len(), 8);
+    let out = segments(item, &[0..1, 10..11, 16..17]);
+    assert_eq!(out.len(), 6);
     let reds = out.iter().filter(|&(_ch, r)| *r).count();
-    assert_eq!(reds, 4);
+    assert_eq!(reds, 3);
+    assert_eq!(out[0], ("f".to_string(), true));
+    assert_eq!(out[2], ("h".to_string(), true));
 }
 
 #[test]
-pub fn test_token_finder2() {
+pub fn test_segments2() {
     let item = "git commit --amend";
-    let text = "g cm a";
-    let out = token_finder(item, text, false);
-    assert_eq!(out.len(), 8);
-    let reds = out.iter().filter(|&(_ch, r)| *r).count();
-    assert_eq!(reds, 4);
+    let out = segments(item, &[0..3, 11..18]);
+    assert_eq!(
+        out,
+        vec![
+            ("git".to_string(), true),
+            (" commit ".to_string(), false),
+            ("--amend".to_string(), true),
+        ]
+    );
+}
+
+#[test]
+pub fn test_segments_multibyte() {
+    let out = segments("café au lait", &[3..4]);
+    assert_eq!(out[1], ("é".to_string(), true));
 }
 
 #[test]
-pub fn test_match_tokens1() {
+pub fn test_match_spans1() {
     let item = "frederick has a kangaroo";
-    let text = " ";
-    let out = match_tokens(item, text, false);
+    let out = match_spans(item, &[], &Theme::default());
     assert_eq!(out.len(), 1);
+    let out = match_spans(item, &[0..9], &Theme::default());
+    assert_eq!(out.len(), 2);
+}
+
+#[test]
+pub fn test_styled_spans_layers_matches_over_syntax() {
+    let line = "git log -n 3";
+    let tokens = tokenize_shell(&[line.to_string()]);
+    let theme = Theme::default();
+    let spans = styled_spans(line, &tokens[0], &[2..5], &theme);
+    let parts: Vec<(&str, Style)> = spans.iter().map(|span| (span.content.as_ref(), span.style)).collect();
+    let matched = theme.syntax(SyntaxKind::Command).patch(theme.matched);
+    assert_eq!(
+        parts,
+        vec![
+            ("gi", theme.command),
+            // matches are drawn over the syntax colours
+            ("t l", matched),
+            ("og ", Style::default()),
+            ("-n", theme.flag),
+            (" 3", Style::default()),
+        ]
+    );
 }
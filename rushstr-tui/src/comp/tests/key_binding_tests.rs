@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rushstr_core::{Action, Keymap, parse_key};
+
+use crate::comp::info_bar::short_key_name;
+use crate::comp::preview_view::preview_title;
+use crate::comp::query_list_view::query_list_title;
+use crate::ux::search_ui::key_name;
+
+fn name(code: KeyCode, modifiers: KeyModifiers) -> Option<String> {
+    key_name(&KeyEvent::new(code, modifiers))
+}
+
+#[test]
+pub fn test_key_names_match_the_configuration() {
+    assert_eq!(name(KeyCode::Char('t'), KeyModifiers::CONTROL).as_deref(), Some("ctrl-t"));
+    assert_eq!(name(KeyCode::Char('T'), KeyModifiers::SHIFT).as_deref(), Some("T"));
+    assert_eq!(name(KeyCode::Enter, KeyModifiers::ALT).as_deref(), Some("alt-enter"));
+    assert_eq!(name(KeyCode::Delete, KeyModifiers::SHIFT).as_deref(), Some("shift-delete"));
+    assert_eq!(name(KeyCode::BackTab, KeyModifiers::SHIFT).as_deref(), Some("shift-tab"));
+    assert_eq!(name(KeyCode::Char(' '), KeyModifiers::CONTROL).as_deref(), Some("ctrl-space"));
+    assert_eq!(name(KeyCode::F(2), KeyModifiers::NONE).as_deref(), Some("f2"));
+    assert_eq!(name(KeyCode::CapsLock, KeyModifiers::NONE), None);
+
+    // what the configuration says is what the key is named
+    for (text, code, modifiers) in [
+        ("Ctrl+T", KeyCode::Char('t'), KeyModifiers::CONTROL),
+        ("shift-t", KeyCode::Char('T'), KeyModifiers::SHIFT),
+        ("shift-tab", KeyCode::BackTab, KeyModifiers::SHIFT),
+        ("ctrl-space", KeyCode::Char(' '), KeyModifiers::CONTROL),
+    ] {
+        assert_eq!(parse_key(text).ok(), name(code, modifiers));
+    }
+}
+
+#[test]
+pub fn test_remapped_keys_reach_their_action() {
+    let keys = BTreeMap::from([(Action::CycleMode, vec!["alt-m".to_string()])]);
+    let keymap = Keymap::new(&keys);
+    let pressed = name(KeyCode::Char('m'), KeyModifiers::ALT).unwrap();
+    assert_eq!(keymap.action(&pressed), Some(Action::CycleMode));
+    let pressed = name(KeyCode::Char('t'), KeyModifiers::CONTROL).unwrap();
+    assert_eq!(keymap.action(&pressed), None);
+}
+
+#[test]
+pub fn test_short_key_names() {
+    assert_eq!(short_key_name("ctrl-x"), "C-x");
+    assert_eq!(short_key_name("ctrl-alt-enter"), "C-M-enter");
+    assert_eq!(short_key_name("f2"), "f2");
+}
+
+#[test]
+pub fn test_preview_title_follows_the_keymap() {
+    assert_eq!(preview_title(&Keymap::new(&BTreeMap::new())), " preview (f2) ");
+    let keys = BTreeMap::from([(Action::TogglePreview, vec!["alt-p".to_string()])]);
+    assert_eq!(preview_title(&Keymap::new(&keys)), " preview (M-p) ");
+    let keys = BTreeMap::from([(Action::TogglePreview, vec![])]);
+    assert_eq!(preview_title(&Keymap::new(&keys)), " preview ");
+}
+
+#[test]
+pub fn test_query_list_title_follows_the_keymap() {
+    assert_eq!(
+        query_list_title(&Keymap::new(&BTreeMap::new())),
+        " recent searches - enter: search again, esc: close "
+    );
+    let keys = BTreeMap::from([(Action::Cancel, vec!["ctrl-q".to_string()])]);
+    assert_eq!(
+        query_list_title(&Keymap::new(&keys)),
+        " recent searches - enter: search again, C-q: close "
+    );
+}
@@ -1 +1,7 @@
 mod item_list_view_tests;
+mod key_binding_tests;
+mod multi_select_tests;
+mod navigation_tests;
+mod preview_view_tests;
+mod query_list_view_tests;
+mod search_box_tests;
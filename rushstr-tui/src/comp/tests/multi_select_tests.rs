@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::KeyCode;
+use rushstr_core::{Action, HItem, Keymap};
+
+use crate::comp::search_box::prompt_spans;
+use crate::ux::search_ui::{BatchAction, joined, paste, prompt_key, targets, toggle_mark};
+use crate::{Prompt, Theme, UiState};
+
+fn items(commands: &[&str]) -> Vec<HItem> {
+    commands
+        .iter()
+        .map(|command| HItem::new(vec![command.to_string()]).unwrap())
+        .collect()
+}
+
+fn commands(items: &[HItem]) -> Vec<String> {
+    items.iter().map(HItem::command).collect()
+}
+
+#[test]
+pub fn test_marks_survive_refiltering() {
+    let all = items(&["cargo build", "cargo test", "git push"]);
+    let mut ui_state = UiState::default();
+    assert_eq!(commands(&targets(&ui_state, &all)), ["cargo build"]);
+
+    ui_state.selected = 2;
+    toggle_mark(&mut ui_state, &all);
+    ui_state.selected = 0;
+    toggle_mark(&mut ui_state, &all);
+    // a new search no longer shows `git push`, which stays marked
+    let filtered = items(&["cargo build", "cargo test"]);
+    ui_state.selected = 1;
+    assert_eq!(commands(&targets(&ui_state, &filtered)), ["git push", "cargo build"]);
+
+    ui_state.selected = 0;
+    toggle_mark(&mut ui_state, &filtered);
+    assert_eq!(commands(&targets(&ui_state, &filtered)), ["git push"]);
+    assert!(!ui_state.is_marked(&filtered[0].id()));
+}
+
+#[test]
+pub fn test_joined() {
+    let chosen = items(&["cargo fmt", "cargo test"]);
+    assert_eq!(joined(&chosen, " && "), "cargo fmt && cargo test");
+    assert_eq!(joined(&chosen, "\n"), "cargo fmt\ncargo test");
+    assert_eq!(joined(&chosen[..1], " && "), "cargo fmt");
+}
+
+/// Presses `code` in a prompt with the default keymap.
+fn key(ui_state: &mut UiState, code: KeyCode) -> Option<BatchAction> {
+    let name = match code {
+        KeyCode::Enter => Some("enter"),
+        KeyCode::Esc => Some("esc"),
+        _ => None,
+    };
+    let action = name.and_then(|name| Keymap::new(&BTreeMap::new()).action(name));
+    prompt_key(ui_state, code, action)
+}
+
+#[test]
+pub fn test_tag_prompt() {
+    let mut ui_state = UiState {
+        prompt: Some(Prompt::Tag(String::new())),
+        ..UiState::default()
+    };
+    assert_eq!(key(&mut ui_state, KeyCode::Enter), None, "an empty tag is not applied");
+    for code in [
+        KeyCode::Char('o'),
+        KeyCode::Char(' '),
+        KeyCode::Char('p'),
+        KeyCode::Char('x'),
+    ] {
+        key(&mut ui_state, code);
+    }
+    key(&mut ui_state, KeyCode::Backspace);
+    key(&mut ui_state, KeyCode::Char('s'));
+    assert_eq!(ui_state.prompt, Some(Prompt::Tag("ops".to_string())));
+    assert_eq!(key(&mut ui_state, KeyCode::Enter), Some(BatchAction::Tag("ops".to_string())));
+    assert_eq!(ui_state.prompt, None);
+
+    ui_state.prompt = Some(Prompt::Tag("ops".to_string()));
+    assert_eq!(key(&mut ui_state, KeyCode::Esc), None);
+    assert_eq!(ui_state.prompt, None);
+}
+
+#[test]
+pub fn test_delete_prompt() {
+    let mut ui_state = UiState {
+        prompt: Some(Prompt::Delete),
+        ..UiState::default()
+    };
+    assert_eq!(key(&mut ui_state, KeyCode::Char('n')), None);
+    assert_eq!(ui_state.prompt, None);
+    ui_state.prompt = Some(Prompt::Delete);
+    assert_eq!(key(&mut ui_state, KeyCode::Char('y')), Some(BatchAction::Delete));
+    assert_eq!(ui_state.prompt, None);
+}
+
+#[test]
+pub fn test_tag_prompt_follows_the_keymap() {
+    let mut ui_state = UiState {
+        prompt: Some(Prompt::Tag("ops".to_string())),
+        ..UiState::default()
+    };
+    // Enter no longer bound to `accept` does not confirm, `ctrl-j` bound to it does
+    assert_eq!(prompt_key(&mut ui_state, KeyCode::Enter, None), None);
+    assert_eq!(
+        prompt_key(&mut ui_state, KeyCode::Char('j'), Some(Action::Accept)),
+        Some(BatchAction::Tag("ops".to_string()))
+    );
+}
+
+#[test]
+pub fn test_paste_goes_to_the_prompt() {
+    let mut ui_state = UiState {
+        prompt: Some(Prompt::Tag("o".to_string())),
+        ..UiState::default()
+    };
+    paste(&mut ui_state, "p s\n");
+    assert_eq!(ui_state.prompt, Some(Prompt::Tag("ops".to_string())));
+    assert!(ui_state.search_options.input.is_empty());
+
+    ui_state.prompt = Some(Prompt::Delete);
+    paste(&mut ui_state, "rm");
+    assert_eq!(ui_state.prompt, Some(Prompt::Delete));
+    assert!(ui_state.search_options.input.is_empty());
+
+    ui_state.prompt = None;
+    paste(&mut ui_state, "git");
+    assert_eq!(ui_state.search_options.input, "git");
+}
+
+#[test]
+pub fn test_prompt_spans() {
+    let text = |prompt: &Prompt, marked: usize| {
+        prompt_spans(prompt, marked, &Theme::default())
+            .iter()
+            .map(|span| span.content.to_string())
+            .collect::<String>()
+    };
+    assert_eq!(text(&Prompt::Tag("de".to_string()), 0), "🦀:tag the selected command:> de");
+    assert_eq!(text(&Prompt::Delete, 1), "🦀:delete 1 marked command? (y/n) ");
+    assert_eq!(text(&Prompt::Delete, 3), "🦀:delete 3 marked commands? (y/n) ");
+}
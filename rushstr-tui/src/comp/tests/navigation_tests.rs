@@ -0,0 +1,59 @@
+use rushstr_core::HItem;
+
+use crate::ux::search_ui::{quick_select_index, select};
+use crate::{ListViewport, UiState};
+
+fn items(lines: &[usize]) -> Vec<HItem> {
+    lines
+        .iter()
+        .map(|count| HItem::new((0..*count).map(|line| format!("echo {line}")).collect()).unwrap())
+        .collect()
+}
+
+#[test]
+pub fn test_page_down_keeps_multi_line_items_visible() {
+    let view = ListViewport::new(&items(&[1, 3, 1, 2, 1, 1, 4, 1]), 80, 4);
+    let mut ui_state = UiState::default();
+
+    let target = view.after(ui_state.selected, view.height());
+    select(&mut ui_state, target, &view);
+    assert_eq!((ui_state.selected, ui_state.offset), (2, 1));
+
+    let target = view.after(ui_state.selected, view.height());
+    select(&mut ui_state, target, &view);
+    assert_eq!((ui_state.selected, ui_state.offset), (5, 3));
+
+    select(&mut ui_state, view.len() - 1, &view);
+    assert_eq!((ui_state.selected, ui_state.offset), (7, 7));
+    select(&mut ui_state, 0, &view);
+    assert_eq!((ui_state.selected, ui_state.offset), (0, 0));
+}
+
+#[test]
+pub fn test_wrapped_items_scroll() {
+    // "echo 0" takes 2 rows in a list 4 cells wide
+    let view = ListViewport::new(&items(&[1, 1, 1]), 4, 3);
+    let mut ui_state = UiState::default();
+    select(&mut ui_state, 1, &view);
+    assert_eq!(ui_state.offset, 1);
+}
+
+#[test]
+pub fn test_quick_select() {
+    let view = ListViewport::new(&items(&[1; 12]), 80, 20);
+    let ui_state = UiState {
+        offset: 1,
+        ..UiState::default()
+    };
+    assert_eq!(quick_select_index(&ui_state, '1', &view), Some(1));
+    assert_eq!(quick_select_index(&ui_state, '9', &view), Some(9));
+    assert_eq!(quick_select_index(&ui_state, '0', &view), Some(10));
+    // only visible items can be picked
+    let short = ListViewport::new(&items(&[1; 12]), 80, 4);
+    assert_eq!(quick_select_index(&ui_state, '5', &short), None);
+
+    let tall = items(&[3, 3]);
+    let ui_state = UiState::default();
+    assert_eq!(quick_select_index(&ui_state, '2', &ListViewport::new(&tall, 80, 5)), None);
+    assert_eq!(quick_select_index(&ui_state, '2', &ListViewport::new(&tall, 80, 6)), Some(1));
+}
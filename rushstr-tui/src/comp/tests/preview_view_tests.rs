@@ -0,0 +1,77 @@
+#![allow(clippy::single_range_in_vec_init)]
+
+use ratatui::layout::Rect;
+use rushstr_core::{HItem, PreviewPosition};
+
+use crate::Theme;
+use crate::comp::preview_view::preview_lines;
+use crate::ux::ui_render_engine::areas;
+
+#[test]
+pub fn test_preview_lines() {
+    let mut item = HItem::new(vec![
+        "for f in *.log; do".to_string(),
+        "  gzip $f".to_string(),
+        "done".to_string(),
+    ])
+    .unwrap()
+    .with_dir("/var/log")
+    .with_exit(1)
+    .with_last_run(1_000)
+    .with_source("~/.bash_history");
+    item.flip_fav();
+    item.add_tag("ops");
+    let lines: Vec<String> = preview_lines(&item, &[vec![2..6]], 1_000 + 7_200, &Theme::default())
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "for f in *.log; do",
+            "  gzip $f",
+            "done",
+            "",
+            "hits     0",
+            "favorite yes ⭐",
+            "tags     ops",
+            "last run 2h ago",
+            "dir      /var/log",
+            "exit     1",
+            "source   ~/.bash_history",
+        ]
+    );
+}
+
+#[test]
+pub fn test_preview_lines_without_metadata() {
+    let item = HItem::new(vec!["ls".to_string()]).unwrap();
+    let lines: Vec<String> = preview_lines(&item, &[], 50, &Theme::default())
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    assert_eq!(lines[3..], ["favorite no", "tags     -", "last run unknown", "source   -"]);
+}
+
+#[test]
+pub fn test_areas() {
+    let wide = Rect::new(0, 0, 160, 40);
+    let narrow = Rect::new(0, 0, 80, 40);
+
+    let plain = areas(wide, None);
+    assert_eq!(plain.len(), 3);
+    assert_eq!(plain[2], Rect::new(0, 2, 160, 38));
+
+    let auto = areas(wide, Some(&PreviewPosition::Auto));
+    assert_eq!(auto.len(), 4);
+    assert_eq!(auto[2].height, 38);
+    assert_eq!(auto[3].x, auto[2].width, "wide terminals get the preview on the right");
+
+    let auto = areas(narrow, Some(&PreviewPosition::Auto));
+    assert_eq!(auto[2].width, 80);
+    assert_eq!(auto[3].y, auto[2].y + auto[2].height, "narrow terminals get the preview below");
+    assert!(auto[2].height < 38);
+
+    assert_eq!(areas(narrow, Some(&PreviewPosition::Right))[2].height, 38);
+    assert_eq!(areas(wide, Some(&PreviewPosition::Bottom))[2].width, 160);
+}
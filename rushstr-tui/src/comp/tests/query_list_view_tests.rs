@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use rushstr_core::{Action, Keymap, RecentQuery, SearchOptions, SearchType};
+
+use crate::comp::query_list_view::query_line;
+use crate::utils::format::age;
+use crate::ux::search_ui::{key_down, key_up_or_recall, query_list_key, recall_newer};
+use crate::{ListViewport, Theme, UiState};
+
+fn recent(input: &str, search_type: SearchType) -> RecentQuery {
+    RecentQuery {
+        input: input.to_string(),
+        search_type,
+        selected: None,
+        time: 1_000,
+    }
+}
+
+fn ui_state() -> UiState {
+    UiState {
+        recent_queries: vec![
+            recent("psql", SearchType::EXACT),
+            recent("deploy prod", SearchType::TYPO),
+        ],
+        ..UiState::default()
+    }
+}
+
+#[test]
+pub fn test_age() {
+    assert_eq!(age(5), "just now");
+    assert_eq!(age(150), "2m ago");
+    assert_eq!(age(7_200), "2h ago");
+    assert_eq!(age(3 * 86_400 + 5), "3d ago");
+}
+
+#[test]
+pub fn test_query_line() {
+    let query = recent("psql", SearchType::EXACT);
+    let line = query_line(&query, 1_000 + 90, &Theme::default());
+    assert_eq!(line.to_string(), "psql  exact 1m ago");
+}
+
+#[test]
+pub fn test_recall_with_empty_search_box() {
+    let mut ui_state = ui_state();
+    key_up_or_recall(&mut ui_state);
+    assert_eq!(ui_state.search_options.input, "psql");
+    assert_eq!(ui_state.search_options.search_type, SearchType::EXACT);
+    key_up_or_recall(&mut ui_state);
+    assert_eq!(ui_state.search_options.input, "deploy prod");
+    assert_eq!(ui_state.search_options.search_type, SearchType::TYPO);
+    // nothing older, the oldest search stays
+    key_up_or_recall(&mut ui_state);
+    assert_eq!(ui_state.recall, Some(1));
+
+    recall_newer(&mut ui_state);
+    assert_eq!(ui_state.search_options.input, "psql");
+    recall_newer(&mut ui_state);
+    assert_eq!(ui_state.search_options.input, "");
+    assert_eq!(ui_state.recall, None);
+}
+
+#[test]
+pub fn test_up_moves_selection_when_typing() {
+    let mut ui_state = UiState {
+        search_options: SearchOptions {
+            input: "git".to_string(),
+            ..SearchOptions::default()
+        },
+        selected: 2,
+        ..ui_state()
+    };
+    key_up_or_recall(&mut ui_state);
+    assert_eq!(ui_state.search_options.input, "git");
+    assert_eq!(ui_state.selected, 1);
+}
+
+#[test]
+pub fn test_up_moves_selection_in_unfiltered_history() {
+    let view = ListViewport::from_heights(&[1, 1, 1, 1], 4);
+    let mut ui_state = ui_state();
+    key_down(&mut ui_state, &view).unwrap();
+    key_down(&mut ui_state, &view).unwrap();
+    key_up_or_recall(&mut ui_state);
+    assert_eq!(ui_state.selected, 1);
+    assert_eq!(ui_state.recall, None);
+    // back on the first item, Up recalls again
+    key_up_or_recall(&mut ui_state);
+    key_up_or_recall(&mut ui_state);
+    assert_eq!(ui_state.search_options.input, "psql");
+}
+
+#[test]
+pub fn test_query_list_keys() {
+    let keymap = Keymap::new(&BTreeMap::new());
+    let mut ui_state = UiState {
+        query_list: Some(0),
+        ..ui_state()
+    };
+    query_list_key(&mut ui_state, keymap.action("down"));
+    query_list_key(&mut ui_state, keymap.action("down"));
+    assert_eq!(ui_state.query_list, Some(1));
+    query_list_key(&mut ui_state, keymap.action("enter"));
+    assert_eq!(ui_state.query_list, None);
+    assert_eq!(ui_state.search_options.input, "deploy prod");
+
+    ui_state.query_list = Some(1);
+    query_list_key(&mut ui_state, keymap.action("ctrl-p"));
+    assert_eq!(ui_state.query_list, Some(0));
+    query_list_key(&mut ui_state, keymap.action("esc"));
+    assert_eq!(ui_state.query_list, None);
+    assert_eq!(ui_state.search_options.input, "deploy prod");
+}
+
+#[test]
+pub fn test_query_list_follows_the_keymap() {
+    let keys = BTreeMap::from([
+        (Action::Down, vec!["alt-j".to_string()]),
+        (Action::Cancel, vec!["ctrl-q".to_string()]),
+    ]);
+    let keymap = Keymap::new(&keys);
+    let mut ui_state = UiState {
+        query_list: Some(0),
+        ..ui_state()
+    };
+    query_list_key(&mut ui_state, keymap.action("down"));
+    assert_eq!(ui_state.query_list, Some(0));
+    query_list_key(&mut ui_state, keymap.action("alt-j"));
+    assert_eq!(ui_state.query_list, Some(1));
+    query_list_key(&mut ui_state, keymap.action("ctrl-q"));
+    assert_eq!(ui_state.query_list, None);
+}
@@ -0,0 +1,33 @@
+use crate::Theme;
+use crate::comp::search_box::{cursor_column, input_spans};
+
+fn contents(input: &str, error_position: Option<usize>) -> Vec<String> {
+    input_spans(input, error_position, &Theme::default())
+        .iter()
+        .map(|span| span.content.to_string())
+        .collect()
+}
+
+#[test]
+pub fn test_input_spans_without_error() {
+    assert_eq!(contents("git (push", None), vec!["git (push"]);
+}
+
+#[test]
+pub fn test_input_spans_marks_error() {
+    assert_eq!(contents("git (push", Some(4)), vec!["git ", "(", "push"]);
+    assert_eq!(contents("日本(", Some(2)), vec!["日本", "(", ""]);
+}
+
+#[test]
+pub fn test_input_spans_error_at_end() {
+    assert_eq!(contents("git (push", Some(9)), vec!["git (push", " "]);
+}
+
+#[test]
+pub fn test_cursor_column_uses_display_width() {
+    assert_eq!(cursor_column(12, "git", 3), 15);
+    assert_eq!(cursor_column(12, "git", 1), 13);
+    assert_eq!(cursor_column(0, "日本語 x", 2), 4);
+    assert_eq!(cursor_column(0, "🦀 x", 2), 3);
+}
@@ -3,6 +3,14 @@ mod types;
 mod utils;
 mod ux;
 
+#[doc(inline)]
+pub use types::line_editor::LineEditor;
+#[doc(inline)]
+pub use types::list_viewport::ListViewport;
+#[doc(inline)]
+pub use types::prompt::Prompt;
+#[doc(inline)]
+pub use types::theme::Theme;
 #[doc(inline)]
 pub use types::ui_state::UiState;
 #[doc(inline)]
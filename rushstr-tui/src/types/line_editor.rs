@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+/// Maximum number of characters in the search input.
+pub const MAX_INPUT_CHARS: usize = 150;
+/// Number of killed texts kept for yanking.
+const KILL_RING_SIZE: usize = 16;
+
+/// Cursor and kill ring of the search box, editing a text owned elsewhere.
+///
+/// Positions are character indices. The cursor is clamped to the text on
+/// every edit, so the text may be replaced without telling the editor.
+#[derive(Debug, Clone, Default)]
+pub struct LineEditor {
+    /// position of the cursor, in characters
+    cursor: usize,
+    /// killed texts, the most recent last
+    kill_ring: Vec<String>,
+    /// characters inserted by the last yank and the kill ring entry they
+    /// come from, while nothing else was done since
+    last_yank: Option<(Range<usize>, usize)>,
+}
+
+impl LineEditor {
+    /// Creates an editor with the cursor at the end of `text`.
+    pub fn at_end(text: &str) -> Self {
+        LineEditor {
+            cursor: text.chars().count(),
+            ..LineEditor::default()
+        }
+    }
+
+    /// Returns the position of the cursor in `text`.
+    pub fn cursor(&self, text: &str) -> usize {
+        self.cursor.min(text.chars().count())
+    }
+
+    /// Inserts `inserted` at the cursor, control characters such as the new
+    /// lines of a paste becoming spaces, as far as the text has room.
+    pub fn insert(&mut self, text: &mut String, inserted: &str) {
+        self.last_yank = None;
+        self.insert_text(text, inserted);
+    }
+
+    /// Deletes the character before the cursor.
+    pub fn delete_backward(&mut self, text: &mut String) {
+        let cursor = self.cursor(text);
+        self.delete(text, cursor.saturating_sub(1)..cursor);
+    }
+
+    /// Deletes the character under the cursor.
+    pub fn delete_forward(&mut self, text: &mut String) {
+        let cursor = self.cursor(text);
+        self.delete(text, cursor..cursor + 1);
+    }
+
+    pub fn move_left(&mut self, text: &str) {
+        self.move_to(self.cursor(text).saturating_sub(1));
+    }
+
+    pub fn move_right(&mut self, text: &str) {
+        self.move_to((self.cursor(text) + 1).min(text.chars().count()));
+    }
+
+    pub fn move_home(&mut self) {
+        self.move_to(0);
+    }
+
+    pub fn move_end(&mut self, text: &str) {
+        self.move_to(text.chars().count());
+    }
+
+    /// Moves to the start of the word before the cursor.
+    pub fn word_left(&mut self, text: &str) {
+        self.move_to(word_start(text, self.cursor(text), char::is_alphanumeric));
+    }
+
+    /// Moves to the end of the word after the cursor.
+    pub fn word_right(&mut self, text: &str) {
+        self.move_to(word_end(text, self.cursor(text), char::is_alphanumeric));
+    }
+
+    /// Kills from the start of the whitespace separated word before the
+    /// cursor to the cursor.
+    pub fn kill_word_backward(&mut self, text: &mut String) {
+        let cursor = self.cursor(text);
+        let start = word_start(text, cursor, |c| !c.is_whitespace());
+        self.kill(text, start..cursor);
+    }
+
+    /// Kills from the cursor to the end of the word after it.
+    pub fn kill_word_forward(&mut self, text: &mut String) {
+        let cursor = self.cursor(text);
+        let end = word_end(text, cursor, char::is_alphanumeric);
+        self.kill(text, cursor..end);
+    }
+
+    /// Kills from the start of the text to the cursor.
+    pub fn kill_to_start(&mut self, text: &mut String) {
+        let cursor = self.cursor(text);
+        self.kill(text, 0..cursor);
+    }
+
+    /// Kills from the cursor to the end of the text.
+    pub fn kill_to_end(&mut self, text: &mut String) {
+        let cursor = self.cursor(text);
+        self.kill(text, cursor..text.chars().count());
+    }
+
+    /// Inserts the most recently killed text at the cursor.
+    pub fn yank(&mut self, text: &mut String) {
+        let Some(last) = self.kill_ring.len().checked_sub(1) else {
+            return;
+        };
+        self.yank_entry(text, last);
+    }
+
+    /// Right after a yank, replaces the yanked text with the text killed
+    /// before it.
+    pub fn yank_pop(&mut self, text: &mut String) {
+        let Some((yanked, entry)) = self.last_yank.take() else {
+            return;
+        };
+        let previous = entry.checked_sub(1).unwrap_or(self.kill_ring.len() - 1);
+        remove_chars(text, yanked.clone());
+        self.cursor = yanked.start;
+        self.yank_entry(text, previous);
+    }
+
+    fn yank_entry(&mut self, text: &mut String, entry: usize) {
+        let start = self.cursor(text);
+        let killed = self.kill_ring[entry].clone();
+        let inserted = self.insert_text(text, &killed);
+        self.last_yank = Some((start..start + inserted, entry));
+    }
+
+    /// Inserts `inserted` at the cursor, returning the number of characters
+    /// inserted.
+    fn insert_text(&mut self, text: &mut String, inserted: &str) -> usize {
+        let room = MAX_INPUT_CHARS.saturating_sub(text.chars().count());
+        let inserted: String = inserted
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .take(room)
+            .collect();
+        let cursor = self.cursor(text);
+        text.insert_str(byte_index(text, cursor), &inserted);
+        let count = inserted.chars().count();
+        self.cursor = cursor + count;
+        count
+    }
+
+    fn delete(&mut self, text: &mut String, range: Range<usize>) {
+        self.last_yank = None;
+        let range = range.start..range.end.min(text.chars().count());
+        if range.is_empty() {
+            return;
+        }
+        remove_chars(text, range.clone());
+        self.cursor = range.start;
+    }
+
+    fn kill(&mut self, text: &mut String, range: Range<usize>) {
+        if range.is_empty() {
+            self.last_yank = None;
+            return;
+        }
+        let killed: String = text.chars().skip(range.start).take(range.len()).collect();
+        if self.kill_ring.len() == KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+        self.kill_ring.push(killed);
+        self.delete(text, range);
+    }
+
+    fn move_to(&mut self, cursor: usize) {
+        self.last_yank = None;
+        self.cursor = cursor;
+    }
+}
+
+/// Returns the byte index of the character at `index` in `text`, or the
+/// length of `text` past its end.
+fn byte_index(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map_or(text.len(), |(byte, _)| byte)
+}
+
+fn remove_chars(text: &mut String, range: Range<usize>) {
+    let start = byte_index(text, range.start);
+    let end = byte_index(text, range.end);
+    text.replace_range(start..end, "");
+}
+
+/// Returns the start of the word before `cursor`, skipping the separators
+/// right before it.
+fn word_start(text: &str, cursor: usize, in_word: impl Fn(char) -> bool) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut index = cursor;
+    while index > 0 && !in_word(chars[index - 1]) {
+        index -= 1;
+    }
+    while index > 0 && in_word(chars[index - 1]) {
+        index -= 1;
+    }
+    index
+}
+
+/// Returns the end of the word after `cursor`, skipping the separators
+/// right after it.
+fn word_end(text: &str, cursor: usize, in_word: impl Fn(char) -> bool) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut index = cursor;
+    while index < chars.len() && !in_word(chars[index]) {
+        index += 1;
+    }
+    while index < chars.len() && in_word(chars[index]) {
+        index += 1;
+    }
+    index
+}
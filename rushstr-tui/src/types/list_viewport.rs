@@ -0,0 +1,84 @@
+use rushstr_core::{HIndex, HItem, HLines};
+
+use crate::utils::calculator::{cumulative_rows, hindex_after_rows, hindex_before_rows, rows_between, scroll_offset};
+
+/// The result list as it is drawn: the rows taken by every item, once its
+/// lines are wrapped at the width of the list, and the height of the list.
+///
+/// Navigation goes through the viewport so the selection stays visible
+/// whatever the height of the items around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListViewport {
+    /// The cumulative rows of the items, see `cumulative_rows`.
+    ends: Vec<HLines>,
+    /// The rows of the list.
+    height: HLines,
+}
+
+impl ListViewport {
+    /// Creates the viewport of `items` drawn in a list `width` cells wide and
+    /// `height` rows high.
+    pub fn new(items: &[HItem], width: u16, height: HLines) -> Self {
+        Self {
+            ends: cumulative_rows(items, width),
+            height,
+        }
+    }
+
+    /// Creates the viewport of items taking `heights` rows each, in a list
+    /// `height` rows high.
+    pub fn from_heights(heights: &[HLines], height: HLines) -> Self {
+        let ends = heights
+            .iter()
+            .scan(0, |total, rows| {
+                *total += rows;
+                Some(*total)
+            })
+            .collect();
+        Self { ends, height }
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Returns whether there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the rows of the list.
+    pub fn height(&self) -> HLines {
+        self.height
+    }
+
+    /// Returns the rows taken by the items from `from` to `to`, both
+    /// included.
+    pub fn rows(&self, from: HIndex, to: HIndex) -> HLines {
+        rows_between(&self.ends, from, to)
+    }
+
+    /// Returns the offset showing `selected` whole, scrolling as little as
+    /// possible from `offset`.
+    pub fn scroll_offset(&self, selected: HIndex, offset: HIndex) -> HIndex {
+        scroll_offset(&self.ends, selected, offset, self.height)
+    }
+
+    /// Returns the index `rows` rows below `hindex`, at least the next one.
+    pub fn after(&self, hindex: HIndex, rows: HLines) -> HIndex {
+        hindex_after_rows(&self.ends, hindex, rows)
+    }
+
+    /// Returns the index `rows` rows above `hindex`, at least the previous
+    /// one.
+    pub fn before(&self, hindex: HIndex, rows: HLines) -> HIndex {
+        hindex_before_rows(&self.ends, hindex, rows)
+    }
+
+    /// Returns whether the item at `hindex` is shown whole when the list
+    /// starts at `offset`.
+    pub fn is_visible(&self, offset: HIndex, hindex: HIndex) -> bool {
+        offset <= hindex && hindex < self.len() && self.rows(offset, hindex) <= self.height
+    }
+}
@@ -1 +1,7 @@
+pub mod line_editor;
+pub mod list_viewport;
+pub mod prompt;
+#[cfg(test)]
+mod tests;
+pub mod theme;
 pub mod ui_state;
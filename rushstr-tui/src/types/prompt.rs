@@ -0,0 +1,9 @@
+/// A question asked in place of the search box before acting on the marked
+/// items, or on the selected item when none is marked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prompt {
+    /// Asks for the tag to add, holding the tag typed so far.
+    Tag(String),
+    /// Asks to confirm the deletion.
+    Delete,
+}
@@ -0,0 +1,140 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::types::line_editor::MAX_INPUT_CHARS;
+use crate::ux::search_ui::edit_key;
+use crate::{LineEditor, UiState};
+
+/// Returns an editor on `text` with the cursor at `cursor`.
+fn editor_at(text: &str, cursor: usize) -> LineEditor {
+    let mut editor = LineEditor::at_end(text);
+    for _ in cursor..text.chars().count() {
+        editor.move_left(text);
+    }
+    editor
+}
+
+#[test]
+pub fn test_insert_and_delete_at_cursor() {
+    let mut text = "gt push".to_string();
+    let mut editor = editor_at(&text, 1);
+    editor.insert(&mut text, "i");
+    assert_eq!(text, "git push");
+    assert_eq!(editor.cursor(&text), 2);
+    editor.delete_forward(&mut text);
+    editor.delete_backward(&mut text);
+    assert_eq!(text, "g push");
+    assert_eq!(editor.cursor(&text), 1);
+}
+
+#[test]
+pub fn test_multibyte_text() {
+    let mut text = "café".to_string();
+    let mut editor = editor_at(&text, 3);
+    editor.delete_forward(&mut text);
+    editor.insert(&mut text, "é!");
+    assert_eq!(text, "café!");
+    editor.move_home();
+    editor.move_right(&text);
+    editor.delete_backward(&mut text);
+    assert_eq!(text, "afé!");
+}
+
+#[test]
+pub fn test_word_motions() {
+    let text = "kubectl get-pods  -n";
+    let mut editor = LineEditor::at_end(text);
+    editor.word_left(text);
+    assert_eq!(editor.cursor(text), 19);
+    editor.word_left(text);
+    assert_eq!(editor.cursor(text), 12);
+    editor.word_left(text);
+    assert_eq!(editor.cursor(text), 8);
+    editor.word_right(text);
+    assert_eq!(editor.cursor(text), 11);
+    editor.move_home();
+    editor.word_right(text);
+    assert_eq!(editor.cursor(text), 7);
+}
+
+#[test]
+pub fn test_kills_and_yank() {
+    let mut text = "docker compose up -d".to_string();
+    let mut editor = LineEditor::at_end(&text);
+    editor.kill_word_backward(&mut text);
+    assert_eq!(text, "docker compose up ");
+    editor.move_home();
+    editor.kill_word_forward(&mut text);
+    assert_eq!(text, " compose up ");
+    editor.kill_to_end(&mut text);
+    assert_eq!(text, "");
+
+    editor.yank(&mut text);
+    assert_eq!(text, " compose up ");
+    // Alt+Y walks back the kill ring in place of the last yank
+    editor.yank_pop(&mut text);
+    assert_eq!(text, "docker");
+    editor.yank_pop(&mut text);
+    assert_eq!(text, "-d");
+    editor.yank_pop(&mut text);
+    assert_eq!(text, " compose up ");
+
+    // once something else is done, Alt+Y does nothing
+    editor.move_home();
+    editor.yank_pop(&mut text);
+    assert_eq!(text, " compose up ");
+    editor.move_end(&text);
+    editor.kill_to_start(&mut text);
+    assert_eq!(text, "");
+}
+
+#[test]
+pub fn test_paste_is_single_line_and_bounded() {
+    let mut text = String::new();
+    let mut editor = LineEditor::default();
+    editor.insert(&mut text, "SELECT 1\nFROM t");
+    assert_eq!(text, "SELECT 1 FROM t");
+    editor.insert(&mut text, &"x".repeat(MAX_INPUT_CHARS));
+    assert_eq!(text.chars().count(), MAX_INPUT_CHARS);
+}
+
+#[test]
+pub fn test_cursor_is_clamped_to_a_replaced_text() {
+    let mut text = "deploy prod".to_string();
+    let mut editor = LineEditor::at_end(&text);
+    text = "ls".to_string();
+    assert_eq!(editor.cursor(&text), 2);
+    editor.insert(&mut text, "!");
+    assert_eq!(text, "ls!");
+}
+
+#[test]
+pub fn test_edit_keys() {
+    let mut ui_state = UiState {
+        selected: 3,
+        ..UiState::default()
+    };
+    let press = |ui_state: &mut UiState, code: KeyCode, modifiers: KeyModifiers| {
+        edit_key(ui_state, KeyEvent::new(code, modifiers));
+    };
+    press(&mut ui_state, KeyCode::Char('L'), KeyModifiers::SHIFT);
+    press(&mut ui_state, KeyCode::Char('s'), KeyModifiers::NONE);
+    press(&mut ui_state, KeyCode::Char('a'), KeyModifiers::CONTROL);
+    press(&mut ui_state, KeyCode::Char('x'), KeyModifiers::ALT);
+    press(&mut ui_state, KeyCode::Char('>'), KeyModifiers::NONE);
+    assert_eq!(ui_state.search_options.input, ">Ls");
+    assert_eq!(ui_state.selected, 0);
+
+    ui_state.selected = 3;
+    press(&mut ui_state, KeyCode::Char('e'), KeyModifiers::CONTROL);
+    assert_eq!(ui_state.selected, 3, "moving the cursor keeps the selection");
+    press(&mut ui_state, KeyCode::Char('w'), KeyModifiers::CONTROL);
+    assert_eq!(ui_state.search_options.input, "");
+
+    // Ctrl+U pages the result list, Alt+U kills to the start
+    ui_state.search_options.input = "kubectl logs".to_string();
+    press(&mut ui_state, KeyCode::Char('u'), KeyModifiers::CONTROL);
+    assert_eq!(ui_state.search_options.input, "kubectl logs");
+    press(&mut ui_state, KeyCode::Char('e'), KeyModifiers::CONTROL);
+    press(&mut ui_state, KeyCode::Char('u'), KeyModifiers::ALT);
+    assert_eq!(ui_state.search_options.input, "");
+}
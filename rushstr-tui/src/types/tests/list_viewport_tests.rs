@@ -0,0 +1,80 @@
+use proptest::prelude::*;
+use ratatui::prelude::Span;
+use rushstr_core::HLines;
+
+use crate::comp::item_list_view::wrap_spans;
+use crate::utils::calculator::line_rows;
+use crate::ux::search_ui::{key_down, select};
+use crate::{ListViewport, UiState};
+
+/// Random item heights, with the height of the list and an index within
+/// the items.
+fn layout() -> impl Strategy<Value = (Vec<HLines>, HLines, usize)> {
+    (prop::collection::vec(1..8usize, 1..60), 1..30usize).prop_flat_map(|(heights, height)| {
+        let len = heights.len();
+        (Just(heights), Just(height), 0..len)
+    })
+}
+
+proptest! {
+    #[test]
+    fn scroll_offset_shows_the_selection((heights, height, selected) in layout(), offset in 0..60usize) {
+        let view = ListViewport::from_heights(&heights, height);
+        let offset = offset.min(heights.len() - 1);
+        let scrolled = view.scroll_offset(selected, offset);
+
+        prop_assert!(scrolled <= selected);
+        // whole, or from its first row when taller than the list
+        prop_assert!(view.rows(scrolled, selected) <= height || scrolled == selected);
+        // scrolling as little as possible
+        if selected >= offset && scrolled > offset {
+            prop_assert!(view.rows(scrolled - 1, selected) > height);
+        }
+        if view.is_visible(offset, selected) {
+            prop_assert_eq!(scrolled, offset);
+        }
+    }
+
+    #[test]
+    fn pages_move_by_at_most_a_page((heights, height, start) in layout()) {
+        let view = ListViewport::from_heights(&heights, height);
+        let last = heights.len() - 1;
+
+        let down = view.after(start, height);
+        prop_assert!(down > start || start == last);
+        prop_assert!(down == start + 1 || down == last || view.rows(start, down - 1) <= height);
+        prop_assert!(down == last || view.rows(start, down) > height);
+
+        let up = view.before(start, height);
+        prop_assert!(up < start || start == 0);
+        prop_assert!(up + 1 == start || up == 0 || view.rows(up + 1, start) <= height);
+        prop_assert!(up == 0 || view.rows(up, start) > height);
+    }
+
+    #[test]
+    fn walking_down_keeps_the_selection_visible((heights, height, _) in layout()) {
+        let view = ListViewport::from_heights(&heights, height);
+        let mut ui_state = UiState::default();
+        for _ in 0..heights.len() {
+            key_down(&mut ui_state, &view).unwrap();
+            let tall = heights[ui_state.selected] > height;
+            prop_assert!(view.is_visible(ui_state.offset, ui_state.selected) || (tall && ui_state.offset == ui_state.selected));
+        }
+        prop_assert_eq!(ui_state.selected, heights.len() - 1);
+
+        select(&mut ui_state, 0, &view);
+        prop_assert_eq!((ui_state.selected, ui_state.offset), (0, 0));
+    }
+
+    #[test]
+    fn wrapping_matches_line_rows(line in "[a-z -]{0,40}|[a-z日本語é ]{0,30}", width in 1..20u16) {
+        let rows = wrap_spans(vec![Span::raw(line.clone())], width);
+        prop_assert_eq!(rows.len(), line_rows(&line, width));
+        let text: String = rows.iter().map(|row| row.to_string()).collect();
+        prop_assert_eq!(text, line);
+        for row in rows {
+            // a single wide character may not fit a one cell row
+            prop_assert!(row.width() <= width.max(2) as usize);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+mod line_editor_tests;
+mod list_viewport_tests;
+mod theme_tests;
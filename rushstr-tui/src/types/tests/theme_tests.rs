@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+
+use ratatui::style::{Color, Modifier, Style};
+use rushstr_core::{SyntaxKind, ThemeElement, ThemeName, UserConfig};
+
+use crate::Theme;
+
+fn styles(theme: &Theme) -> Vec<Style> {
+    let mut styles = vec![
+        theme.selection,
+        theme.matched,
+        theme.info_bar,
+        theme.info_value,
+        theme.info_active,
+        theme.marked,
+        theme.error,
+        theme.invalid,
+        theme.notice,
+        theme.prompt,
+        theme.prompt_arrow,
+        theme.dim,
+    ];
+    styles.extend(
+        [
+            SyntaxKind::Command,
+            SyntaxKind::Flag,
+            SyntaxKind::String,
+            SyntaxKind::Variable,
+            SyntaxKind::Operator,
+            SyntaxKind::Comment,
+        ]
+        .map(|kind| theme.syntax(kind)),
+    );
+    styles
+}
+
+#[test]
+pub fn test_built_in_themes() {
+    assert_eq!(Theme::named(&ThemeName::Default), Theme::default());
+    assert_eq!(Theme::default().selection.bg, Some(Color::LightYellow));
+    for name in [ThemeName::Light, ThemeName::Solarized, ThemeName::Mono] {
+        let theme = Theme::named(&name);
+        assert_ne!(theme, Theme::default(), "{name}");
+        // the selection and the matches must stand out in every theme
+        assert_ne!(theme.selection, Style::default(), "{name}");
+        assert_ne!(theme.matched, Style::default(), "{name}");
+    }
+}
+
+#[test]
+pub fn test_overrides_replace_whole_styles() {
+    let overrides = BTreeMap::from([
+        (ThemeElement::Selection, "reversed".to_string()),
+        (ThemeElement::Comment, "#586e75 on 236".to_string()),
+    ]);
+    let theme = Theme::new(&ThemeName::Light, &overrides);
+    assert_eq!(theme.selection, Style::default().add_modifier(Modifier::REVERSED));
+    assert_eq!(
+        theme.syntax(SyntaxKind::Comment),
+        Style::default()
+            .fg(Color::Rgb(0x58, 0x6e, 0x75))
+            .bg(Color::Indexed(236))
+    );
+    assert_eq!(theme.matched, Theme::light().matched);
+}
+
+#[test]
+pub fn test_no_color_uses_no_colours() {
+    let config = UserConfig::parse("[ui]\ntheme = \"solarized\"\n\n[colors]\nmatch = \"red\"\n").unwrap();
+    assert_eq!(Theme::from_config(&config, false).matched.fg, Some(Color::Red));
+
+    let theme = Theme::from_config(&config, true);
+    assert_eq!(theme, Theme::mono());
+    for style in styles(&theme) {
+        assert_eq!((style.fg, style.bg), (None, None));
+    }
+    assert!(theme.selection.add_modifier.contains(Modifier::REVERSED));
+}
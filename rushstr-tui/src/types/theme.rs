@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+
+use ratatui::style::{Color, Modifier, Style};
+use rushstr_core::{ColorSpec, StyleModifier, StyleSpec, SyntaxKind, ThemeElement, ThemeName, UserConfig};
+
+/// The styles of the search UI.
+///
+/// A theme starts from one of the built-in themes, then takes the styles of
+/// the `[colors]` table of the configuration. When the `NO_COLOR`
+/// environment variable is set the `mono` theme is used instead, whatever
+/// the configuration says.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    /// The selected command, and the selected recent search.
+    pub selection: Style,
+    /// The characters matched by the search.
+    pub matched: Style,
+    /// The info bar, the styles of its parts are patched over it.
+    pub info_bar: Style,
+    /// The values shown in the info bar.
+    pub info_value: Style,
+    /// The hint of a filter of the info bar while it is enabled.
+    pub info_active: Style,
+    /// The marks of the marked commands, and their count.
+    pub marked: Style,
+    /// Errors and the deletion prompt.
+    pub error: Style,
+    /// The invalid part of the search input.
+    pub invalid: Style,
+    /// Warnings and debug values.
+    pub notice: Style,
+    /// The label of the search box and the text typed in it.
+    pub prompt: Style,
+    /// The arrow of the search box.
+    pub prompt_arrow: Style,
+    /// Labels and details of less importance.
+    pub dim: Style,
+    pub command: Style,
+    pub flag: Style,
+    pub string: Style,
+    pub variable: Style,
+    pub operator: Style,
+    pub comment: Style,
+}
+
+impl Default for Theme {
+    /// Returns the `default` theme, made for dark terminals.
+    fn default() -> Self {
+        Theme {
+            selection: Style::default()
+                .bg(Color::LightYellow)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            matched: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            info_bar: Style::default()
+                .bg(Color::Black)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            info_value: Style::default().fg(Color::Blue),
+            info_active: Style::default().fg(Color::Cyan),
+            marked: Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            error: Style::default().fg(Color::Red),
+            invalid: Style::default().fg(Color::White).bg(Color::Red),
+            notice: Style::default().fg(Color::Yellow),
+            prompt: Style::default().fg(Color::Green),
+            prompt_arrow: Style::default().fg(Color::Cyan),
+            dim: Style::default().fg(Color::DarkGray),
+            command: Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+            flag: Style::default().fg(Color::Cyan),
+            string: Style::default().fg(Color::Yellow),
+            variable: Style::default().fg(Color::Magenta),
+            operator: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            comment: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        }
+    }
+}
+
+impl Theme {
+    /// Returns the theme of `config`, or the `mono` theme when `no_color`
+    /// is set.
+    pub fn from_config(config: &UserConfig, no_color: bool) -> Theme {
+        if no_color {
+            return Theme::mono();
+        }
+        Theme::new(&config.ui.theme, &config.colors)
+    }
+
+    /// Returns the built-in theme `name` with the styles of `overrides`.
+    ///
+    /// An override replaces the whole style of its element. Overrides that
+    /// do not parse are skipped, `UserConfig::parse` reports them.
+    pub fn new(name: &ThemeName, overrides: &BTreeMap<ThemeElement, String>) -> Theme {
+        let mut theme = Theme::named(name);
+        for (element, text) in overrides {
+            if let Ok(spec) = StyleSpec::parse(text) {
+                *theme.style_mut(*element) = style(&spec);
+            }
+        }
+        theme
+    }
+
+    /// Returns the built-in theme `name`.
+    pub fn named(name: &ThemeName) -> Theme {
+        match name {
+            ThemeName::Default => Theme::default(),
+            ThemeName::Light => Theme::light(),
+            ThemeName::Solarized => Theme::solarized(),
+            ThemeName::Mono => Theme::mono(),
+        }
+    }
+
+    /// Returns the `light` theme: dark text, pale selection and info bar.
+    pub fn light() -> Theme {
+        Theme {
+            selection: Style::default()
+                .bg(Color::LightCyan)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            matched: Style::default()
+                .fg(Color::Red)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            info_bar: Style::default()
+                .bg(Color::Gray)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            info_value: Style::default().fg(Color::Blue),
+            info_active: Style::default().fg(Color::Magenta),
+            marked: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            error: Style::default().fg(Color::Red),
+            invalid: Style::default().fg(Color::White).bg(Color::Red),
+            notice: Style::default().fg(Color::Magenta),
+            prompt: Style::default().fg(Color::Blue),
+            prompt_arrow: Style::default().fg(Color::Magenta),
+            dim: Style::default().fg(Color::DarkGray),
+            command: Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            flag: Style::default().fg(Color::Magenta),
+            string: Style::default().fg(Color::Green),
+            variable: Style::default().fg(Color::Cyan),
+            operator: Style::default().fg(Color::Black).add_modifier(Modifier::BOLD),
+            comment: Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+        }
+    }
+
+    /// Returns the `solarized` theme, made of the accents and the base
+    /// colours the dark and light Solarized backgrounds share.
+    pub fn solarized() -> Theme {
+        const BASE02: Color = Color::Rgb(0x07, 0x36, 0x42);
+        const BASE01: Color = Color::Rgb(0x58, 0x6e, 0x75);
+        const BASE1: Color = Color::Rgb(0x93, 0xa1, 0xa1);
+        const BASE3: Color = Color::Rgb(0xfd, 0xf6, 0xe3);
+        const YELLOW: Color = Color::Rgb(0xb5, 0x89, 0x00);
+        const ORANGE: Color = Color::Rgb(0xcb, 0x4b, 0x16);
+        const RED: Color = Color::Rgb(0xdc, 0x32, 0x2f);
+        const MAGENTA: Color = Color::Rgb(0xd3, 0x36, 0x82);
+        const VIOLET: Color = Color::Rgb(0x6c, 0x71, 0xc4);
+        const BLUE: Color = Color::Rgb(0x26, 0x8b, 0xd2);
+        const CYAN: Color = Color::Rgb(0x2a, 0xa1, 0x98);
+        const GREEN: Color = Color::Rgb(0x85, 0x99, 0x00);
+        Theme {
+            selection: Style::default().bg(BLUE).fg(BASE3).add_modifier(Modifier::BOLD),
+            matched: Style::default()
+                .fg(ORANGE)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            info_bar: Style::default().bg(BASE02).fg(BASE1).add_modifier(Modifier::BOLD),
+            info_value: Style::default().fg(BLUE),
+            info_active: Style::default().fg(CYAN),
+            marked: Style::default().fg(GREEN).add_modifier(Modifier::BOLD),
+            error: Style::default().fg(RED),
+            invalid: Style::default().fg(BASE3).bg(RED),
+            notice: Style::default().fg(YELLOW),
+            prompt: Style::default().fg(GREEN),
+            prompt_arrow: Style::default().fg(CYAN),
+            dim: Style::default().fg(BASE01),
+            command: Style::default().fg(GREEN).add_modifier(Modifier::BOLD),
+            flag: Style::default().fg(CYAN),
+            string: Style::default().fg(YELLOW),
+            variable: Style::default().fg(MAGENTA),
+            operator: Style::default().fg(VIOLET).add_modifier(Modifier::BOLD),
+            comment: Style::default().fg(BASE01).add_modifier(Modifier::ITALIC),
+        }
+    }
+
+    /// Returns the `mono` theme, which uses no colour: the selection and
+    /// the invalid input are reversed, what stands out is bold.
+    pub fn mono() -> Theme {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        Theme {
+            selection: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            matched: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            info_bar: Style::default().add_modifier(Modifier::REVERSED),
+            info_value: bold,
+            info_active: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            marked: bold,
+            error: bold,
+            invalid: Style::default().add_modifier(Modifier::REVERSED),
+            notice: bold,
+            prompt: bold,
+            prompt_arrow: Style::default(),
+            dim: Style::default(),
+            command: bold,
+            flag: Style::default(),
+            string: Style::default(),
+            variable: Style::default(),
+            operator: bold,
+            comment: Style::default(),
+        }
+    }
+
+    /// Returns the style of a shell token of the given `kind`.
+    pub fn syntax(&self, kind: SyntaxKind) -> Style {
+        match kind {
+            SyntaxKind::Command => self.command,
+            SyntaxKind::Flag => self.flag,
+            SyntaxKind::String => self.string,
+            SyntaxKind::Variable => self.variable,
+            SyntaxKind::Operator => self.operator,
+            SyntaxKind::Comment => self.comment,
+        }
+    }
+
+    fn style_mut(&mut self, element: ThemeElement) -> &mut Style {
+        match element {
+            ThemeElement::Selection => &mut self.selection,
+            ThemeElement::Match => &mut self.matched,
+            ThemeElement::InfoBar => &mut self.info_bar,
+            ThemeElement::InfoValue => &mut self.info_value,
+            ThemeElement::InfoActive => &mut self.info_active,
+            ThemeElement::Marked => &mut self.marked,
+            ThemeElement::Error => &mut self.error,
+            ThemeElement::Invalid => &mut self.invalid,
+            ThemeElement::Notice => &mut self.notice,
+            ThemeElement::Prompt => &mut self.prompt,
+            ThemeElement::PromptArrow => &mut self.prompt_arrow,
+            ThemeElement::Dim => &mut self.dim,
+            ThemeElement::Command => &mut self.command,
+            ThemeElement::Flag => &mut self.flag,
+            ThemeElement::String => &mut self.string,
+            ThemeElement::Variable => &mut self.variable,
+            ThemeElement::Operator => &mut self.operator,
+            ThemeElement::Comment => &mut self.comment,
+        }
+    }
+}
+
+/// Returns whether colours are disabled, following <https://no-color.org>:
+/// `NO_COLOR` is set to a non-empty value.
+pub(crate) fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Converts a style of the configuration.
+fn style(spec: &StyleSpec) -> Style {
+    let mut style = Style::default();
+    if let Some(fg) = spec.fg {
+        style = style.fg(color(fg));
+    }
+    if let Some(bg) = spec.bg {
+        style = style.bg(color(bg));
+    }
+    for modifier in &spec.modifiers {
+        style = style.add_modifier(match modifier {
+            StyleModifier::Bold => Modifier::BOLD,
+            StyleModifier::Dim => Modifier::DIM,
+            StyleModifier::Italic => Modifier::ITALIC,
+            StyleModifier::Underlined => Modifier::UNDERLINED,
+            StyleModifier::Reversed => Modifier::REVERSED,
+        });
+    }
+    style
+}
+
+/// Converts a colour of the configuration.
+fn color(spec: ColorSpec) -> Color {
+    match spec {
+        ColorSpec::Reset => Color::Reset,
+        ColorSpec::Black => Color::Black,
+        ColorSpec::Red => Color::Red,
+        ColorSpec::Green => Color::Green,
+        ColorSpec::Yellow => Color::Yellow,
+        ColorSpec::Blue => Color::Blue,
+        ColorSpec::Magenta => Color::Magenta,
+        ColorSpec::Cyan => Color::Cyan,
+        ColorSpec::Gray => Color::Gray,
+        ColorSpec::DarkGray => Color::DarkGray,
+        ColorSpec::LightRed => Color::LightRed,
+        ColorSpec::LightGreen => Color::LightGreen,
+        ColorSpec::LightYellow => Color::LightYellow,
+        ColorSpec::LightBlue => Color::LightBlue,
+        ColorSpec::LightMagenta => Color::LightMagenta,
+        ColorSpec::LightCyan => Color::LightCyan,
+        ColorSpec::White => Color::White,
+        ColorSpec::Indexed(index) => Color::Indexed(index),
+        ColorSpec::Rgb(red, green, blue) => Color::Rgb(red, green, blue),
+    }
+}
@@ -1,4 +1,6 @@
-use rushstr_core::{HIndex, HLines, SearchOptions};
+use rushstr_core::{HIndex, HItem, HLines, Key, QueryError, RecentQuery, SearchOptions};
+
+use crate::{LineEditor, Prompt};
 
 /// Represents the current state of the UI, including selection,
 /// scrolling offset, debug mode, and search input configuration.
@@ -22,10 +24,46 @@ pub struct UiState {
     /// When enabled, additional debug information may be rendered or logged.
     pub debug: bool,
 
+    /// Whether the preview of the selected item is shown.
+    pub preview: bool,
+
     /// The search options used for filtering and matching UI entries.
     ///
     /// This includes search text and case sensitivity preferences.
     pub search_options: SearchOptions,
+
+    /// The cursor and kill ring of the search box, editing
+    /// `search_options.input`.
+    pub editor: LineEditor,
+
+    /// Why the current search input cannot be run, if it cannot.
+    ///
+    /// While set, the results of the last valid search stay on screen.
+    pub query_error: Option<QueryError>,
+
+    /// The searches of previous sessions, most recent first.
+    pub recent_queries: Vec<RecentQuery>,
+
+    /// The index in `recent_queries` of the search recalled in the search
+    /// box, while the recalled input is left untouched.
+    pub recall: Option<usize>,
+
+    /// The selected row of the recent searches list, while the list is
+    /// open.
+    pub query_list: Option<usize>,
+
+    /// The items marked with Tab, in the order they were marked.
+    ///
+    /// Items are kept whole so they stay marked, and can still be output,
+    /// while a new search filters them out.
+    pub marked: Vec<HItem>,
+
+    /// The question asked in place of the search box, if any.
+    pub prompt: Option<Prompt>,
+
+    /// A warning shown in the info bar until the next key press, such as
+    /// the keys bound to several actions.
+    pub notice: Option<String>,
 }
 
 impl Default for UiState {
@@ -33,13 +71,35 @@ impl Default for UiState {
     /// - `selected`: 0 (first item)
     /// - `offset`: 0 (top of the list)
     /// - `debug`: false
+    /// - `preview`: false
     /// - `search_options`: empty search input and default config
+    /// - `editor`: cursor at the start of the empty input
+    /// - `query_error`: none
+    /// - `recent_queries`: empty, nothing recalled and the list closed
+    /// - `marked`: empty, with no prompt
+    /// - `notice`: none
     fn default() -> Self {
         UiState {
             selected: 0,
             offset: 0,
             debug: false,
+            preview: false,
             search_options: SearchOptions::default(),
+            editor: LineEditor::default(),
+            query_error: None,
+            recent_queries: Vec::new(),
+            recall: None,
+            query_list: None,
+            marked: Vec::new(),
+            prompt: None,
+            notice: None,
         }
     }
 }
+
+impl UiState {
+    /// Returns whether the item with the given ID is marked.
+    pub fn is_marked(&self, id: &Key) -> bool {
+        self.marked.iter().any(|item| item.id() == *id)
+    }
+}
@@ -1,4 +1,5 @@
 use rushstr_core::{HIndex, HItem, HLines};
+use unicode_width::UnicodeWidthChar;
 
 /// Converts a history index (`hindex`) into the total number of visual lines
 /// (`hlines`) occupied by the history items up to and including that index.
@@ -25,6 +26,96 @@ pub fn hindex_to_hlines(items: &[HItem], hindex: HIndex) -> HLines {
     items[..=hindex].iter().map(|item| item.hlines()).sum()
 }
 
+/// Returns the rows `line` takes once wrapped at `width` terminal cells. A
+/// character that does not fit on a row starts the next one, wide
+/// characters taking two cells, and an empty line still takes a row.
+pub fn line_rows(line: &str, width: u16) -> HLines {
+    let mut rows = 1;
+    let mut used = 0;
+    for c in line.chars() {
+        let cells = c.width().unwrap_or(0);
+        if used > 0 && used + cells > width as usize {
+            rows += 1;
+            used = 0;
+        }
+        used += cells;
+    }
+    rows
+}
+
+/// Returns the rows `item` takes in a list `width` cells wide, each line of
+/// the command wrapping on its own.
+pub fn item_rows(item: &HItem, width: u16) -> HLines {
+    item.command_lines().iter().map(|line| line_rows(line, width)).sum()
+}
+
+/// Returns the cumulative rows of `items` in a list `width` cells wide: the
+/// rows taken by the items up to and including every index. Without
+/// wrapping, entry `i` equals `hindex_to_hlines(items, i)`.
+pub fn cumulative_rows(items: &[HItem], width: u16) -> Vec<HLines> {
+    items
+        .iter()
+        .scan(0, |total, item| {
+            *total += item_rows(item, width);
+            Some(*total)
+        })
+        .collect()
+}
+
+/// Returns the rows taken by the items from `from` to `to`, both included,
+/// given their cumulative rows `ends`, or 0 when `from` comes after `to`.
+pub fn rows_between(ends: &[HLines], from: HIndex, to: HIndex) -> HLines {
+    if from > to || ends.is_empty() {
+        return 0;
+    }
+    let to = to.min(ends.len() - 1);
+    let before = from.checked_sub(1).map_or(0, |before| ends[before]);
+    ends[to].saturating_sub(before)
+}
+
+/// Returns the offset, the index of the first visible item, which keeps
+/// `selected` whole in a list `height` rows high while scrolling as little
+/// as possible from `offset`. An item taller than the list is shown from
+/// its first row.
+pub fn scroll_offset(ends: &[HLines], selected: HIndex, offset: HIndex, height: HLines) -> HIndex {
+    if selected <= offset {
+        return selected;
+    }
+    // every item takes at least one row
+    let mut offset = offset.max((selected + 1).saturating_sub(height));
+    while offset < selected && rows_between(ends, offset, selected) > height {
+        offset += 1;
+    }
+    offset
+}
+
+/// Returns the index reached by moving `rows` rows down from `hindex`,
+/// moving at least one item unless `hindex` is the last one.
+pub fn hindex_after_rows(ends: &[HLines], hindex: HIndex, rows: HLines) -> HIndex {
+    let last = ends.len().saturating_sub(1);
+    let mut target = hindex;
+    while target < last && rows_between(ends, hindex, target) <= rows {
+        target += 1;
+    }
+    if target == hindex && hindex < last {
+        target += 1;
+    }
+    target.min(last)
+}
+
+/// Returns the index reached by moving `rows` rows up from `hindex`,
+/// moving at least one item unless `hindex` is the first one.
+pub fn hindex_before_rows(ends: &[HLines], hindex: HIndex, rows: HLines) -> HIndex {
+    let mut target = hindex.min(ends.len().saturating_sub(1));
+    while target > 0 && rows_between(ends, target, hindex) <= rows {
+        target -= 1;
+    }
+    if target == hindex && hindex > 0 {
+        target -= 1;
+    }
+    target
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -63,6 +154,64 @@ mod tests {
         assert_eq!(hindex_to_hlines(&items, 2), 6); // 1 + 2 + 3
     }
 
+    #[test]
+    fn test_line_rows() {
+        assert_eq!(line_rows("", 4), 1);
+        assert_eq!(line_rows("abcd", 4), 1);
+        assert_eq!(line_rows("abcde", 4), 2);
+        // a wide character which does not fit goes to the next row
+        assert_eq!(line_rows("abc日", 4), 2);
+        assert_eq!(line_rows("日本語", 4), 2);
+        assert_eq!(line_rows("日本", 1), 2);
+    }
+
+    #[test]
+    fn test_cumulative_rows() {
+        let items = vec![
+            hitem_with_lines(&["a"]),
+            hitem_with_lines(&["echo one two", "b"]),
+            hitem_with_lines(&["c"]),
+        ];
+        assert_eq!(cumulative_rows(&items, 80), vec![1, 3, 4]);
+        assert_eq!(cumulative_rows(&items, 80)[2], hindex_to_hlines(&items, 2));
+        assert_eq!(cumulative_rows(&items, 5), vec![1, 5, 6]);
+    }
+
+    #[test]
+    fn test_rows_between() {
+        let ends = vec![1, 3, 4];
+        assert_eq!(rows_between(&ends, 0, 2), 4);
+        assert_eq!(rows_between(&ends, 1, 1), 2);
+        assert_eq!(rows_between(&ends, 2, 1), 0);
+        assert_eq!(rows_between(&ends, 1, 9), 3);
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        // ten items of 2 rows, a 5 row list shows 2 of them
+        let ends: Vec<HLines> = (1..=10).map(|index| index * 2).collect();
+        assert_eq!(scroll_offset(&ends, 1, 0, 5), 0);
+        assert_eq!(scroll_offset(&ends, 2, 0, 5), 1);
+        assert_eq!(scroll_offset(&ends, 9, 0, 5), 8);
+        assert_eq!(scroll_offset(&ends, 3, 5, 5), 3);
+        assert_eq!(scroll_offset(&ends, 4, 0, 1), 4);
+    }
+
+    #[test]
+    fn test_page_moves() {
+        // items of 1, 3, 1, 1 and 1 rows
+        let ends = vec![1, 4, 5, 6, 7];
+        assert_eq!(hindex_after_rows(&ends, 0, 3), 1);
+        assert_eq!(hindex_after_rows(&ends, 0, 4), 2);
+        assert_eq!(hindex_after_rows(&ends, 1, 1), 2, "at least one item");
+        assert_eq!(hindex_after_rows(&ends, 3, 10), 4);
+        assert_eq!(hindex_after_rows(&ends, 4, 10), 4);
+        assert_eq!(hindex_before_rows(&ends, 4, 2), 2);
+        assert_eq!(hindex_before_rows(&ends, 4, 5), 1);
+        assert_eq!(hindex_before_rows(&ends, 2, 1), 1, "at least one item");
+        assert_eq!(hindex_before_rows(&ends, 0, 3), 0);
+    }
+
     #[test]
     fn test_hindex_exactly_last() {
         let items = vec![
@@ -0,0 +1,9 @@
+/// Formats a number of seconds as a short age, e.g. `5m ago`.
+pub fn age(seconds: u64) -> String {
+    match seconds {
+        0..60 => "just now".to_string(),
+        60..3_600 => format!("{}m ago", seconds / 60),
+        3_600..86_400 => format!("{}h ago", seconds / 3_600),
+        _ => format!("{}d ago", seconds / 86_400),
+    }
+}
@@ -1,2 +1,2 @@
 pub mod search_ui;
-mod ui_render_engine;
+pub(crate) mod ui_render_engine;
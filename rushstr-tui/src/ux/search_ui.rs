@@ -1,92 +1,163 @@
 use std::io::stdout;
+use std::ops::ControlFlow;
 
 use arboard::Clipboard;
 use crossterm::event::{
-    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event, KeyCode, KeyEvent,
+    KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
 };
 use crossterm::{event, execute};
-use ratatui::DefaultTerminal;
-use rushstr_core::{HItem, Store};
+use ratatui::{DefaultTerminal, TerminalOptions, Viewport};
+use rushstr_core::{Action, HItem, Keymap, RecentQuery, SearchOptions, SessionState, Store, UserConfig};
 
-use crate::UiState;
-use crate::ux::ui_render_engine::UiRenderEngine;
+use crate::comp::item_list_view::content_width;
+use crate::types::theme::no_color;
+use crate::ux::ui_render_engine::{UiRenderEngine, areas};
+use crate::{LineEditor, ListViewport, Prompt, Theme, UiState};
+
+/// Joins the commands of marked items output with Enter.
+const AND_SEPARATOR: &str = " && ";
+/// Joins the commands of marked items output with Alt+Enter or copied.
+const LINE_SEPARATOR: &str = "\n";
+
+/// The items chosen when the UI closes, and how their commands are joined.
+struct Choice {
+    items: Vec<HItem>,
+    separator: &'static str,
+}
 
 pub struct SearchUI {
     store: Store,
+    config: UserConfig,
+    keymap: Keymap,
+    theme: Theme,
 }
 
 impl SearchUI {
-    pub fn new(store: &Store) -> SearchUI {
-        SearchUI { store: store.clone() }
+    pub fn new(store: &Store, config: &UserConfig) -> SearchUI {
+        SearchUI {
+            store: store.clone(),
+            config: config.clone(),
+            keymap: config.keymap(),
+            theme: Theme::from_config(config, no_color()),
+        }
     }
 
-    pub fn search(&self) -> anyhow::Result<Option<String>> {
-        let mut terminal = ratatui::init();
+    /// Runs the interactive search starting from `search_options`.
+    ///
+    /// The search settings in use when the UI closes are saved as the
+    /// session to restore next time, and a non-empty query is added to the
+    /// recent searches with the item selected. Several marked items are
+    /// output joined with `&&` or newlines.
+    pub fn search(&self, search_options: SearchOptions) -> anyhow::Result<Option<String>> {
+        let mut terminal = match self.config.ui.height {
+            Some(height) => {
+                ratatui::init_with_options(TerminalOptions {
+                    viewport: Viewport::Inline(height),
+                })
+            },
+            None => ratatui::init(),
+        };
         terminal.clear()?;
-        execute!(stdout(), EnableMouseCapture)?;
+        execute!(stdout(), EnableMouseCapture, EnableBracketedPaste)?;
 
-        let result = self.search_items(&mut terminal);
+        let mut ui_state = UiState {
+            editor: LineEditor::at_end(&search_options.input),
+            search_options,
+            recent_queries: self.store.recent_queries().unwrap_or_default(),
+            preview: self.config.ui.preview,
+            notice: notice(&self.keymap),
+            ..UiState::default()
+        };
+        let result = self.search_items(&mut terminal, &mut ui_state);
 
         // === Ensure terminal is cleanly restored before printing ===
-        execute!(stdout(), DisableMouseCapture)?;
+        execute!(stdout(), DisableMouseCapture, DisableBracketedPaste)?;
         ratatui::restore();
 
-        result
+        // failing to remember the session must not discard the selection
+        let _ = self.store.save_session(&SessionState::from(&ui_state.search_options));
+        if !ui_state.search_options.input.trim().is_empty() {
+            // only a single selection is learned
+            let selected = match &result {
+                Ok(Some(Choice { items, .. })) if items.len() == 1 => Some(items[0].id()),
+                _ => None,
+            };
+            let _ = self
+                .store
+                .save_query(&RecentQuery::new(&ui_state.search_options, selected));
+        }
+        result.map(|choice| choice.map(|choice| joined(&choice.items, choice.separator)))
     }
 
-    fn search_items(&self, terminal: &mut DefaultTerminal) -> anyhow::Result<Option<String>> {
-        let mut ui_state = UiState::default();
+    fn search_items(&self, terminal: &mut DefaultTerminal, ui_state: &mut UiState) -> anyhow::Result<Option<Choice>> {
+        let mut items = Vec::new();
         loop {
-            let items = self.store.items(&ui_state.search_options).ok().unwrap_or(vec![]);
-            let height = (terminal.size()?.height - 2) as usize;
-            terminal.draw(|frame| UiRenderEngine::new(&items, &ui_state, &self.store).render(frame))?;
+            // an invalid query keeps the last valid results on screen
+            match self.store.items(&ui_state.search_options) {
+                Ok(found) => {
+                    items = found;
+                    ui_state.query_error = None;
+                },
+                Err(error) => ui_state.query_error = Some(error),
+            }
+            // deleting items can leave the selection past the end
+            ui_state.selected = ui_state.selected.min(items.len().saturating_sub(1));
+            ui_state.offset = ui_state.offset.min(ui_state.selected);
+            let preview = ui_state.preview.then_some(&self.config.ui.preview_position);
+            let list = areas(terminal.get_frame().area(), preview)[2];
+            let view = ListViewport::new(&items, content_width(ui_state, list.width), list.height as usize);
+            // the list may have been resized or rewrapped since the last key
+            ui_state.offset = view.scroll_offset(ui_state.selected, ui_state.offset);
+            terminal.draw(|frame| {
+                UiRenderEngine::new(&items, ui_state, &self.store, &self.config.ui, &self.keymap, &self.theme)
+                    .render(frame)
+            })?;
 
             match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press && ui_state.query_list.is_some() => {
+                    query_list_key(ui_state, self.action(&key))
+                },
+                Event::Key(key) if key.kind == KeyEventKind::Press && ui_state.prompt.is_some() => {
+                    if let Some(action) = prompt_key(ui_state, key.code, self.action(&key)) {
+                        self.apply(ui_state, &items, action)?;
+                    }
+                },
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    match key.code {
-                        KeyCode::Esc => return Ok(None),
-                        KeyCode::Char('x') if key.modifiers == KeyModifiers::CONTROL => {
-                            if let Some(item) = items.get(ui_state.selected) {
-                                self.store.mark_favorite(&item.id());
+                    ui_state.notice = None;
+                    match self.action(&key) {
+                        Some(action) => {
+                            if let ControlFlow::Break(choice) = self.run(action, ui_state, &items, &view)? {
+                                return Ok(choice);
                             }
                         },
-                        KeyCode::Char('f') if key.modifiers == KeyModifiers::CONTROL => {
-                            ui_state.search_options.favorites = !ui_state.search_options.favorites;
-                        },
-                        KeyCode::Char('d') if key.modifiers == KeyModifiers::CONTROL => {
-                            ui_state.debug = !ui_state.debug
-                        },
-                        KeyCode::Char('t') if key.modifiers == KeyModifiers::CONTROL => {
-                            ui_state.search_options.search_type = ui_state.search_options.search_type.next()
-                        },
-                        KeyCode::Char('c') if key.modifiers == KeyModifiers::CONTROL => {
-                            if let Some(cmd) = items.get(ui_state.selected) {
-                                copy_to_clipboard(cmd);
+                        None => {
+                            match key.code {
+                                KeyCode::Char(digit)
+                                    if key.modifiers == KeyModifiers::ALT && digit.is_ascii_digit() =>
+                                {
+                                    if let Some(index) = quick_select_index(ui_state, digit, &view) {
+                                        self.store.mark_hit(&items[index].id());
+                                        return Ok(Some(Choice {
+                                            items: vec![items[index].clone()],
+                                            separator: AND_SEPARATOR,
+                                        }));
+                                    }
+                                },
+                                _ => edit_key(ui_state, key),
                             }
-                            return Ok(None);
-                        },
-                        KeyCode::Up => key_up(&mut ui_state),
-                        KeyCode::Down => {
-                            key_down(&mut ui_state, height, &items)?;
                         },
-                        KeyCode::Enter => {
-                            return get_selected(&items, &ui_state, &self.store);
-                        },
-                        KeyCode::Char(c) => put_char(&mut ui_state, c),
-                        KeyCode::Backspace => backspace(&mut ui_state),
-                        _ => {},
                     }
                 },
+                Event::Paste(text) => paste(ui_state, &text),
                 Event::Mouse(mouse_event) => {
                     match mouse_event.kind {
-                        MouseEventKind::ScrollUp => key_up(&mut ui_state),
+                        MouseEventKind::ScrollUp => key_up(ui_state),
                         MouseEventKind::ScrollDown => {
-                            key_down(&mut ui_state, height, &items)?;
+                            key_down(ui_state, &view)?;
                         },
-                        MouseEventKind::Down(button) => {
-                            if button == MouseButton::Middle {
-                                return get_selected(&items, &ui_state, &self.store);
-                            }
+                        MouseEventKind::Down(MouseButton::Middle) if ui_state.prompt.is_none() => {
+                            return get_selected(&items, ui_state, &self.store, AND_SEPARATOR);
                         },
                         _ => {},
                     }
@@ -95,70 +166,365 @@ impl SearchUI {
             }
         }
     }
+
+    /// Returns the action the keymap binds to `key`.
+    fn action(&self, key: &KeyEvent) -> Option<Action> {
+        key_name(key).and_then(|name| self.keymap.action(&name))
+    }
+
+    /// Runs the action bound to the key pressed.
+    ///
+    /// Breaks with the items chosen when the action closes the UI.
+    fn run(
+        &self,
+        action: Action,
+        ui_state: &mut UiState,
+        items: &[HItem],
+        view: &ListViewport,
+    ) -> anyhow::Result<ControlFlow<Option<Choice>>> {
+        let half_page = (view.height() / 2).max(1);
+        match action {
+            Action::Cancel if !ui_state.marked.is_empty() => ui_state.marked.clear(),
+            Action::Cancel => return Ok(ControlFlow::Break(None)),
+            Action::Accept => {
+                return Ok(ControlFlow::Break(get_selected(items, ui_state, &self.store, AND_SEPARATOR)?));
+            },
+            Action::AcceptLines => {
+                return Ok(ControlFlow::Break(get_selected(items, ui_state, &self.store, LINE_SEPARATOR)?));
+            },
+            Action::Copy => {
+                let targets = targets(ui_state, items);
+                if !targets.is_empty() {
+                    copy_to_clipboard(&joined(&targets, LINE_SEPARATOR));
+                }
+                return Ok(ControlFlow::Break(None));
+            },
+            Action::ToggleFavorite => {
+                let favorite = !targets(ui_state, items).iter().all(HItem::is_fav);
+                self.apply(ui_state, items, BatchAction::Favorite(favorite))?;
+            },
+            Action::FavoritesOnly => {
+                let favorites = !ui_state.search_options.favorites_only();
+                ui_state.search_options.set_favorites_only(favorites);
+            },
+            Action::CycleMode => {
+                let current = &ui_state.search_options.search_type;
+                ui_state.search_options.search_type = self.config.search.next_search_type(current)
+            },
+            Action::CycleRanking => ui_state.search_options.ranking = ui_state.search_options.ranking.next(),
+            Action::CycleCase => ui_state.search_options.case_mode = ui_state.search_options.case_mode.next(),
+            Action::ToggleScope => ui_state.search_options.scope = ui_state.search_options.scope.next(),
+            Action::RecentSearches => {
+                if !ui_state.recent_queries.is_empty() {
+                    ui_state.query_list = Some(0);
+                }
+            },
+            Action::Up => key_up_or_recall(ui_state),
+            Action::Down => {
+                // the recalled search is kept, Up moves in its results
+                ui_state.recall = None;
+                key_down(ui_state, view)?;
+            },
+            Action::RecallNewer if ui_state.recall.is_some() => recall_newer(ui_state),
+            Action::RecallNewer => key_down(ui_state, view)?,
+            Action::PageUp => select(ui_state, view.before(ui_state.selected, view.height()), view),
+            Action::PageDown => select(ui_state, view.after(ui_state.selected, view.height()), view),
+            Action::HalfPageUp => select(ui_state, view.before(ui_state.selected, half_page), view),
+            Action::HalfPageDown => select(ui_state, view.after(ui_state.selected, half_page), view),
+            Action::First => select(ui_state, 0, view),
+            Action::Last => select(ui_state, items.len().saturating_sub(1), view),
+            Action::Mark => {
+                toggle_mark(ui_state, items);
+                key_down(ui_state, view)?;
+            },
+            Action::Tag => {
+                if !targets(ui_state, items).is_empty() {
+                    ui_state.prompt = Some(Prompt::Tag(String::new()));
+                }
+            },
+            Action::Delete => {
+                if !targets(ui_state, items).is_empty() {
+                    ui_state.prompt = Some(Prompt::Delete);
+                }
+            },
+            Action::TogglePreview => ui_state.preview = !ui_state.preview,
+            Action::ToggleDebug => ui_state.debug = !ui_state.debug,
+        }
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Applies `action` to the marked items, or to the selected item when
+    /// none is marked, in the store and in the marked copies.
+    fn apply(&self, ui_state: &mut UiState, items: &[HItem], action: BatchAction) -> anyhow::Result<()> {
+        for target in targets(ui_state, items) {
+            let id = target.id();
+            match &action {
+                BatchAction::Favorite(favorite) => self.store.set_favorite(&id, *favorite),
+                BatchAction::Tag(tag) => self.store.add_tag(&id, tag),
+                BatchAction::Delete => self.store.delete(&id)?,
+            }
+        }
+        match action {
+            BatchAction::Favorite(favorite) => {
+                for item in ui_state.marked.iter_mut().filter(|item| item.is_fav() != favorite) {
+                    item.flip_fav();
+                }
+            },
+            BatchAction::Tag(tag) => ui_state.marked.iter_mut().for_each(|item| item.add_tag(&tag)),
+            BatchAction::Delete => ui_state.marked.clear(),
+        }
+        Ok(())
+    }
+}
+
+/// A change made to the marked items, or to the selected item.
+#[derive(Debug, PartialEq)]
+pub(crate) enum BatchAction {
+    Favorite(bool),
+    Tag(String),
+    Delete,
+}
+
+/// Returns the warning shown at startup about the keys of `keymap` bound
+/// to several actions, if any.
+fn notice(keymap: &Keymap) -> Option<String> {
+    let conflicts: Vec<String> = keymap.conflicts().iter().map(ToString::to_string).collect();
+    (!conflicts.is_empty()).then(|| conflicts.join(", "))
 }
 
-/// Copies the raw text of a command to the system clipboard.
+/// Returns the name of `key` in the keymap, such as `ctrl-t` or `alt-enter`.
+///
+/// Shift is part of the characters it types, so `T` is named `T` and not
+/// `shift-t`.
+///
+/// # Arguments
+///
+/// * `key` - The key pressed.
+///
+/// # Returns
+///
+/// * `None` for keys the keymap cannot bind, such as media keys.
+pub(crate) fn key_name(key: &KeyEvent) -> Option<String> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    let name = match key.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => return Some(rushstr_core::key_name(ctrl, alt, false, &c.to_string())),
+        KeyCode::BackTab => return Some(rushstr_core::key_name(ctrl, alt, true, "tab")),
+        KeyCode::F(number) => format!("f{number}"),
+        KeyCode::Enter => "enter".to_string(),
+        KeyCode::Esc => "esc".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Delete => "delete".to_string(),
+        KeyCode::Insert => "insert".to_string(),
+        KeyCode::Home => "home".to_string(),
+        KeyCode::End => "end".to_string(),
+        KeyCode::PageUp => "pageup".to_string(),
+        KeyCode::PageDown => "pagedown".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        _ => return None,
+    };
+    Some(rushstr_core::key_name(ctrl, alt, shift, &name))
+}
+
+/// Copies `text` to the system clipboard.
 ///
 /// This function first attempts to use the system clipboard via the `arboard`
 /// crate.
 ///
 /// # Parameters
-/// - `cmd`: A reference to the `HItem` whose raw text will be copied.
+/// - `text`: The text to copy, such as the raw text of a command.
 #[allow(clippy::collapsible_if)]
-fn copy_to_clipboard(cmd: &HItem) {
+fn copy_to_clipboard(text: &str) {
     // Try using system clipboard via arboard
     if let Ok(mut clipboard) = Clipboard::new() {
-        let _ = clipboard.set_text(cmd.raw_text());
+        let _ = clipboard.set_text(text);
     }
 }
 
-/// Returns the raw text of the currently selected item in the list, if any.
-/// Also marks the item as "hit" in the store.
+/// Returns the marked items, or the currently selected item when none is
+/// marked. Also marks the returned items as "hit" in the store.
 ///
 /// # Arguments
 ///
 /// * `items` - A slice of `HItem` representing all the items.
 /// * `ui_state` - The current UI state, which includes the selected index.
-/// * `store` - The backing store, used to mark the selected item as accessed.
+/// * `store` - The backing store, used to mark the chosen items as accessed.
+/// * `separator` - Joins the commands of several items.
 ///
 /// # Returns
 ///
-/// * `Ok(Some(String))` if a valid item is selected, containing the item's raw
-///   text.
+/// * `Ok(Some(Choice))` if items are marked or a valid item is selected.
 /// * `Ok(None)` if the selection index is out of bounds.
-fn get_selected(items: &[HItem], ui_state: &UiState, store: &Store) -> anyhow::Result<Option<String>> {
-    if let Some(h_item) = items.get(ui_state.selected) {
+fn get_selected(
+    items: &[HItem],
+    ui_state: &UiState,
+    store: &Store,
+    separator: &'static str,
+) -> anyhow::Result<Option<Choice>> {
+    let chosen = targets(ui_state, items);
+    if chosen.is_empty() {
+        return Ok(None);
+    }
+    for h_item in &chosen {
         store.mark_hit(&h_item.id());
-        return Ok(Some(h_item.raw_text()));
     }
-    Ok(None)
+    Ok(Some(Choice {
+        items: chosen,
+        separator,
+    }))
+}
+
+/// Returns the items an action applies to: the marked items, or the
+/// currently selected item when none is marked.
+///
+/// # Arguments
+///
+/// * `ui_state` - The current UI state, with the marked items.
+/// * `items` - The items of the current search.
+pub(crate) fn targets(ui_state: &UiState, items: &[HItem]) -> Vec<HItem> {
+    if ui_state.marked.is_empty() {
+        items.get(ui_state.selected).cloned().into_iter().collect()
+    } else {
+        ui_state.marked.clone()
+    }
+}
+
+/// Joins the raw text of the commands of `items` with `separator`.
+pub(crate) fn joined(items: &[HItem], separator: &str) -> String {
+    items.iter().map(HItem::raw_text).collect::<Vec<_>>().join(separator)
 }
 
-/// Appends a character to the search input, as long as the input is less than
-/// 50 characters. Resets the selection and scroll offset to the top.
+/// Marks the selected item, or unmarks it when it is already marked.
 ///
 /// # Arguments
 ///
 /// * `ui_state` - Mutable reference to the UI state.
-/// * `char` - The character to append to the input.
-fn put_char(ui_state: &mut UiState, char: char) {
-    if ui_state.search_options.input.len() < 150 {
-        ui_state.search_options.input.push(char);
-        ui_state.selected = 0;
-        ui_state.offset = 0;
+/// * `items` - The items of the current search.
+pub(crate) fn toggle_mark(ui_state: &mut UiState, items: &[HItem]) {
+    let Some(item) = items.get(ui_state.selected) else {
+        return;
+    };
+    if ui_state.is_marked(&item.id()) {
+        ui_state.marked.retain(|marked| marked.id() != item.id());
+    } else {
+        ui_state.marked.push(item.clone());
     }
 }
 
-/// Removes the last character from the search input (backspace behavior).
-/// Also resets the selection and scroll offset to the top.
+/// Handles a key while a prompt is shown: a tag is typed and confirmed with
+/// the keys of `accept`, a deletion is confirmed with `y`. The keys of
+/// `cancel`, or any other key for a deletion, close the prompt.
 ///
 /// # Arguments
 ///
 /// * `ui_state` - Mutable reference to the UI state.
-fn backspace(ui_state: &mut UiState) {
-    ui_state.search_options.input.pop();
-    ui_state.selected = 0;
-    ui_state.offset = 0;
+/// * `code` - The key pressed.
+/// * `action` - The action the keymap binds to the key, if any.
+///
+/// # Returns
+///
+/// The action confirmed, if any.
+pub(crate) fn prompt_key(ui_state: &mut UiState, code: KeyCode, action: Option<Action>) -> Option<BatchAction> {
+    match (ui_state.prompt.as_mut()?, code) {
+        (Prompt::Tag(tag), _) if action == Some(Action::Accept) => {
+            if tag.is_empty() {
+                return None;
+            }
+            let tag = std::mem::take(tag);
+            ui_state.prompt = None;
+            return Some(BatchAction::Tag(tag));
+        },
+        (Prompt::Tag(_), _) if action == Some(Action::Cancel) => ui_state.prompt = None,
+        (Prompt::Tag(tag), KeyCode::Char(c)) if !c.is_whitespace() => tag.push(c),
+        (Prompt::Tag(tag), KeyCode::Backspace) => {
+            tag.pop();
+        },
+        (Prompt::Delete, KeyCode::Char('y' | 'Y')) => {
+            ui_state.prompt = None;
+            return Some(BatchAction::Delete);
+        },
+        (Prompt::Delete, _) => ui_state.prompt = None,
+        _ => {},
+    }
+    None
+}
+
+/// Inserts pasted `text` where keys would type it: into the tag of the tag
+/// prompt, without whitespace, or into the search input. It is dropped while
+/// the deletion prompt or the recent searches are shown.
+///
+/// # Arguments
+///
+/// * `ui_state` - Mutable reference to the UI state.
+/// * `text` - The text pasted.
+pub(crate) fn paste(ui_state: &mut UiState, text: &str) {
+    match ui_state.prompt.as_mut() {
+        Some(Prompt::Tag(tag)) => tag.extend(text.chars().filter(|c| !c.is_whitespace())),
+        Some(Prompt::Delete) => {},
+        None if ui_state.query_list.is_none() => edit_input(ui_state, |editor, input| editor.insert(input, text)),
+        None => {},
+    }
+}
+
+/// Edits the search input with `key`, emacs style: Ctrl+A/E and Alt+B/F
+/// move, Ctrl+W/K and Alt+D/U kill, Ctrl+Y and Alt+Y yank. Ctrl+U goes to
+/// the result list, which pages with it.
+///
+/// # Arguments
+///
+/// * `ui_state` - Mutable reference to the UI state.
+/// * `key` - The key pressed.
+pub(crate) fn edit_key(ui_state: &mut UiState, key: KeyEvent) {
+    let control = key.modifiers == KeyModifiers::CONTROL;
+    let alt = key.modifiers == KeyModifiers::ALT;
+    edit_input(ui_state, |editor, input| {
+        match key.code {
+            KeyCode::Left if control => editor.word_left(input),
+            KeyCode::Right if control => editor.word_right(input),
+            KeyCode::Left => editor.move_left(input),
+            KeyCode::Right => editor.move_right(input),
+            KeyCode::Backspace if alt || control => editor.kill_word_backward(input),
+            KeyCode::Backspace => editor.delete_backward(input),
+            KeyCode::Delete => editor.delete_forward(input),
+            KeyCode::Char('a') if control => editor.move_home(),
+            KeyCode::Char('e') if control => editor.move_end(input),
+            KeyCode::Char('w') if control => editor.kill_word_backward(input),
+            KeyCode::Char('u') if alt => editor.kill_to_start(input),
+            KeyCode::Char('k') if control => editor.kill_to_end(input),
+            KeyCode::Char('y') if control => editor.yank(input),
+            KeyCode::Char('b') if alt => editor.word_left(input),
+            KeyCode::Char('f') if alt => editor.word_right(input),
+            KeyCode::Char('d') if alt => editor.kill_word_forward(input),
+            KeyCode::Char('y') if alt => editor.yank_pop(input),
+            KeyCode::Char(c) if key.modifiers.difference(KeyModifiers::SHIFT).is_empty() => {
+                editor.insert(input, c.encode_utf8(&mut [0; 4]))
+            },
+            _ => {},
+        }
+    });
+}
+
+/// Applies `edit` to the search input. A changed input ends the recall and
+/// resets the selection and scroll offset to the top.
+///
+/// # Arguments
+///
+/// * `ui_state` - Mutable reference to the UI state.
+/// * `edit` - The edit, given the line editor and the input.
+fn edit_input(ui_state: &mut UiState, edit: impl FnOnce(&mut LineEditor, &mut String)) {
+    let before = ui_state.search_options.input.clone();
+    edit(&mut ui_state.editor, &mut ui_state.search_options.input);
+    if ui_state.search_options.input != before {
+        ui_state.recall = None;
+        ui_state.selected = 0;
+        ui_state.offset = 0;
+    }
 }
 
 /// Moves the selection up by one, ensuring it does not go below zero.
@@ -181,19 +547,120 @@ fn key_up(ui_state: &mut UiState) {
 /// # Arguments
 ///
 /// * `ui_state` - Mutable reference to the UI state.
-/// * `list_height` - The number of visible lines in the UI list.
-/// * `items` - A slice of `HItem` representing all the items.
+/// * `view` - The rows taken by the items in the UI list.
 ///
 /// # Returns
 ///
 /// * `Ok(())` on success.
-fn key_down(ui_state: &mut UiState, list_height: usize, items: &[HItem]) -> anyhow::Result<()> {
-    if ui_state.selected + 1 >= items.len() {
+pub(crate) fn key_down(ui_state: &mut UiState, view: &ListViewport) -> anyhow::Result<()> {
+    if ui_state.selected + 1 >= view.len() {
         return Ok(());
     }
-    ui_state.selected += 1;
-    if ui_state.selected >= ui_state.offset + list_height {
-        ui_state.offset = ui_state.selected + 1 - list_height;
-    }
+    select(ui_state, ui_state.selected + 1, view);
     Ok(())
 }
+
+/// Selects the item at `hindex` and scrolls the list as little as possible
+/// to show it whole, counting the rows of multi-line and wrapped items.
+///
+/// # Arguments
+///
+/// * `ui_state` - Mutable reference to the UI state.
+/// * `hindex` - The index of the item to select.
+/// * `view` - The rows taken by the items in the UI list.
+pub(crate) fn select(ui_state: &mut UiState, hindex: usize, view: &ListViewport) {
+    ui_state.selected = hindex;
+    ui_state.offset = view.scroll_offset(hindex, ui_state.offset);
+}
+
+/// Returns the index of the visible item picked by `digit`, hstr style:
+/// `1` to `9` pick the first nine visible items and `0` the tenth.
+///
+/// # Arguments
+///
+/// * `ui_state` - The current UI state, with the scroll offset.
+/// * `digit` - The digit pressed.
+/// * `view` - The rows taken by the items in the UI list.
+pub(crate) fn quick_select_index(ui_state: &UiState, digit: char, view: &ListViewport) -> Option<usize> {
+    let position = match digit.to_digit(10)? {
+        0 => 9,
+        digit => digit as usize - 1,
+    };
+    let index = ui_state.offset + position;
+    view.is_visible(ui_state.offset, index).then_some(index)
+}
+
+/// Moves the selection up by one, or recalls the previous search when the
+/// first item is selected in an empty search box, or when the search box
+/// already shows a recalled search.
+///
+/// # Arguments
+///
+/// * `ui_state` - Mutable reference to the UI state.
+pub(crate) fn key_up_or_recall(ui_state: &mut UiState) {
+    let at_top = ui_state.search_options.input.is_empty() && ui_state.selected == 0;
+    if at_top || ui_state.recall.is_some() {
+        let older = ui_state.recall.map_or(0, |index| index + 1);
+        if older < ui_state.recent_queries.len() {
+            recall(ui_state, Some(older));
+        }
+    } else {
+        key_up(ui_state);
+    }
+}
+
+/// Recalls the search made after the recalled one, or empties the search box
+/// after the most recent search.
+///
+/// # Arguments
+///
+/// * `ui_state` - Mutable reference to the UI state.
+pub(crate) fn recall_newer(ui_state: &mut UiState) {
+    match ui_state.recall {
+        Some(0) => recall(ui_state, None),
+        Some(index) => recall(ui_state, Some(index - 1)),
+        None => {},
+    }
+}
+
+/// Puts the recent search at `index` in the search box, with its search
+/// type, or empties the search box when `index` is `None`.
+fn recall(ui_state: &mut UiState, index: Option<usize>) {
+    match index.and_then(|index| ui_state.recent_queries.get(index)) {
+        Some(query) => {
+            ui_state.search_options.input = query.input.clone();
+            ui_state.search_options.search_type = query.search_type.clone();
+        },
+        None => ui_state.search_options.input.clear(),
+    }
+    ui_state.editor.move_end(&ui_state.search_options.input);
+    ui_state.recall = index;
+    ui_state.selected = 0;
+    ui_state.offset = 0;
+}
+
+/// Handles a key while the recent searches list is open, given the action
+/// the keymap binds to it: `up` and `down` (or `recall_newer`) move in the
+/// list, `accept` searches again with the selected search, and `cancel` or
+/// `recent_searches` close the list.
+///
+/// # Arguments
+///
+/// * `ui_state` - Mutable reference to the UI state.
+/// * `action` - The action bound to the key pressed, if any.
+pub(crate) fn query_list_key(ui_state: &mut UiState, action: Option<Action>) {
+    let Some(selected) = ui_state.query_list else {
+        return;
+    };
+    let last = ui_state.recent_queries.len().saturating_sub(1);
+    match action {
+        Some(Action::Up) => ui_state.query_list = Some(selected.saturating_sub(1)),
+        Some(Action::Down | Action::RecallNewer) => ui_state.query_list = Some((selected + 1).min(last)),
+        Some(Action::Accept) => {
+            recall(ui_state, Some(selected));
+            ui_state.query_list = None;
+        },
+        Some(Action::Cancel | Action::RecentSearches) => ui_state.query_list = None,
+        _ => {},
+    }
+}
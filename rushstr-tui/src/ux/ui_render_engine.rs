@@ -1,45 +1,102 @@
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Direction, Layout};
-use rushstr_core::{HItem, Store};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use rushstr_core::{HItem, Highlighter, Keymap, PreviewPosition, Store, UiConfig};
 
-use crate::UiState;
 use crate::comp::info_bar::InfoBar;
 use crate::comp::item_list_view::ItemListView;
+use crate::comp::preview_view::PreviewView;
+use crate::comp::query_list_view::QueryListView;
 use crate::comp::search_box::SearchBox;
+use crate::{Theme, UiState};
+
+/// Width from which an automatically placed preview goes on the right.
+const WIDE_TERMINAL: u16 = 120;
 
 pub struct UiRenderEngine<'f> {
     items: &'f [HItem],
     store: &'f Store,
     search_options: &'f UiState,
+    config: &'f UiConfig,
+    keymap: &'f Keymap,
+    theme: &'f Theme,
 }
 
 impl<'f> UiRenderEngine<'f> {
-    pub fn new(items: &'f [HItem], search_options: &'f UiState, store: &'f Store) -> Self {
+    pub fn new(
+        items: &'f [HItem],
+        search_options: &'f UiState,
+        store: &'f Store,
+        config: &'f UiConfig,
+        keymap: &'f Keymap,
+        theme: &'f Theme,
+    ) -> Self {
         Self {
             items,
             search_options,
             store,
+            config,
+            keymap,
+            theme,
         }
     }
 
     pub fn render(&self, frame: &mut Frame) {
-        let layout = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(0)
-            .constraints([
-                Constraint::Length(1), // Search box
-                Constraint::Length(1), // InfoBar
-                Constraint::Min(1),    // List
-            ])
-            .split(frame.area());
+        let preview = self.search_options.preview.then_some(&self.config.preview_position);
+        let layout = areas(frame.area(), preview);
 
         // ─── Render Search Bar ─────────────────────────────────────
-        SearchBox::new(self.search_options, &layout).render(frame);
+        SearchBox::new(self.search_options, self.theme, &layout).render(frame);
 
         // ─── Render Header Line ─────────────────────────────────────
-        InfoBar::new(self.items, self.search_options, self.store, &layout).render(frame);
+        InfoBar::new(self.items, self.search_options, self.store, self.keymap, self.theme, &layout).render(frame);
 
         // ─── Render Result List ─────────────────────────────────────
-        ItemListView::new(self.items, self.search_options, &layout).render(frame);
+        let highlighter = Highlighter::new(&self.search_options.search_options, self.store.matchers());
+        ItemListView::new(self.items, self.search_options, &highlighter, self.theme, &layout).render(frame);
+
+        // ─── Render Preview ─────────────────────────────────────────
+        if let Some(area) = layout.get(3) {
+            let selected = self.items.get(self.search_options.selected);
+            PreviewView::new(selected, &highlighter, self.keymap, self.theme, *area).render(frame);
+        }
+
+        // ─── Render Recent Searches Over The List ───────────────────
+        QueryListView::new(self.search_options, self.keymap, self.theme, &layout).render(frame);
     }
 }
+
+/// Splits `area` into the search box, the info bar, the result list and,
+/// when `preview` is set, the preview.
+///
+/// An `Auto` preview goes on the right of terminals at least
+/// `WIDE_TERMINAL` columns wide and below the results otherwise.
+pub(crate) fn areas(area: Rect, preview: Option<&PreviewPosition>) -> Vec<Rect> {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(0)
+        .constraints([
+            Constraint::Length(1), // Search box
+            Constraint::Length(1), // InfoBar
+            Constraint::Min(1),    // List
+        ])
+        .split(area);
+    let mut areas = rows.to_vec();
+    let Some(position) = preview else {
+        return areas;
+    };
+
+    let (direction, constraints) = match position {
+        PreviewPosition::Right => (Direction::Horizontal, [Constraint::Percentage(55), Constraint::Percentage(45)]),
+        PreviewPosition::Auto if area.width >= WIDE_TERMINAL => {
+            (Direction::Horizontal, [Constraint::Percentage(55), Constraint::Percentage(45)])
+        },
+        _ => (Direction::Vertical, [Constraint::Percentage(60), Constraint::Percentage(40)]),
+    };
+    let split = Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(rows[2]);
+    areas[2] = split[0];
+    areas.push(split[1]);
+    areas
+}
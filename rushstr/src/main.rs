@@ -2,17 +2,25 @@ use std::rc::Rc;
 
 use anyhow::Result;
 use clap::Parser;
-use rushstr_core::{ArgsHandler, ConfigOptions, HistoryCrawler, Scanner, Store, VectorStore};
+use rushstr_core::{ArgsHandler, ConfigOptions, HistoryCrawler, Scanner, Store, UserConfig, VectorStore};
 use rushstr_tui::SearchUI;
 
 fn main() -> Result<()> {
     let args = ConfigOptions::parse();
-    if ArgsHandler::new(args).execute()? {
+    if ArgsHandler::new(args.clone()).execute()? {
         return Ok(());
     }
-    let scanner: Scanner = Rc::new(HistoryCrawler::new());
-    let store: Store = Rc::new(VectorStore::new(scanner)?);
-    if let Some(text) = SearchUI::new(&store).search()? {
+    let config = UserConfig::from_options(&args)?;
+    let scanner: Scanner = Rc::new(HistoryCrawler::with_config(&config.history)?);
+    let store: Store = Rc::new(VectorStore::new(scanner, args.data_dir.as_deref())?);
+    config.check_search_types(store.matchers())?;
+    let session = store.load_session()?;
+    let mut search_options = config.startup_options(session.as_ref(), &args);
+    if store.matchers().get(&search_options.search_type).is_none() {
+        // e.g. restored from a session of a build with more matchers
+        search_options.search_type = config.search.mode.clone();
+    }
+    if let Some(text) = SearchUI::new(&store, &config).search(search_options)? {
         eprint!("{}", text.trim_end());
     }
     Ok(())